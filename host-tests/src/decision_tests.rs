@@ -0,0 +1,81 @@
+// `decision::decide`: thresholds, ties, the anomaly gate.
+
+use crate::config::EI_LABEL_COUNT;
+use crate::decision::{decide, Decision, Scores};
+
+const THRESHOLDS: Scores = [0.7; EI_LABEL_COUNT];
+const NO_ANOMALY_GATE: f32 = f32::INFINITY;
+
+fn decision(index: usize, confidence: f32) -> Option<Decision> {
+    Some(Decision { index, confidence })
+}
+
+#[test]
+fn single_dominant_class_wins() {
+    let scores = [0.05, 0.90, 0.03, 0.02];
+    assert_eq!(decide(&scores, &THRESHOLDS, None, NO_ANOMALY_GATE), decision(1, 0.90));
+}
+
+#[test]
+fn nothing_when_every_class_is_below_its_threshold() {
+    let scores = [0.40, 0.30, 0.20, 0.10];
+    assert_eq!(decide(&scores, &THRESHOLDS, None, NO_ANOMALY_GATE), None);
+
+    // Just under counts as under.
+    let scores = [0.69, 0.0, 0.0, 0.31];
+    assert_eq!(decide(&scores, &THRESHOLDS, None, NO_ANOMALY_GATE), None);
+}
+
+#[test]
+fn a_score_equal_to_its_threshold_clears_it() {
+    let scores = [0.0, 0.0, 0.7, 0.3];
+    assert_eq!(decide(&scores, &THRESHOLDS, None, NO_ANOMALY_GATE), decision(2, 0.7));
+}
+
+#[test]
+fn each_class_is_held_to_its_own_threshold() {
+    // Idle scores highest but misses its (higher) threshold; snake clears its own.
+    let thresholds = [0.9, 0.5, 0.7, 0.85];
+    let scores = [0.60, 0.55, 0.0, 0.0];
+    assert_eq!(decide(&scores, &thresholds, None, NO_ANOMALY_GATE), decision(1, 0.55));
+}
+
+#[test]
+fn the_highest_of_several_passing_classes_wins() {
+    let thresholds = [0.3; EI_LABEL_COUNT];
+    let scores = [0.35, 0.0, 0.45, 0.40];
+    assert_eq!(decide(&scores, &thresholds, None, NO_ANOMALY_GATE), decision(2, 0.45));
+}
+
+#[test]
+fn ties_go_to_the_earlier_class() {
+    let thresholds = [0.3; EI_LABEL_COUNT];
+    let scores = [0.1, 0.45, 0.0, 0.45];
+    assert_eq!(decide(&scores, &thresholds, None, NO_ANOMALY_GATE), decision(1, 0.45));
+
+    let scores = [0.5, 0.5, 0.0, 0.0];
+    assert_eq!(decide(&scores, &thresholds, None, NO_ANOMALY_GATE), decision(0, 0.5));
+}
+
+#[test]
+fn nan_scores_never_win() {
+    let thresholds = [f32::NEG_INFINITY; EI_LABEL_COUNT];
+    let scores = [f32::NAN, 0.2, f32::NAN, 0.1];
+    assert_eq!(decide(&scores, &thresholds, None, NO_ANOMALY_GATE), decision(1, 0.2));
+}
+
+#[test]
+fn an_anomalous_window_decides_nothing() {
+    let scores = [0.05, 0.90, 0.03, 0.02];
+    assert_eq!(decide(&scores, &THRESHOLDS, Some(0.31), 0.3), None);
+    assert_eq!(decide(&scores, &THRESHOLDS, Some(5.0), 0.3), None);
+}
+
+#[test]
+fn the_anomaly_gate_passes_scores_at_or_below_it() {
+    let scores = [0.05, 0.90, 0.03, 0.02];
+    assert_eq!(decide(&scores, &THRESHOLDS, Some(0.3), 0.3), decision(1, 0.90));
+    assert_eq!(decide(&scores, &THRESHOLDS, Some(0.0), 0.3), decision(1, 0.90));
+    // No anomaly block in the impulse: no gate at all.
+    assert_eq!(decide(&scores, &THRESHOLDS, None, 0.0), decision(1, 0.90));
+}
@@ -0,0 +1,167 @@
+// `input::InputManager`: the exact events a press sequence produces.
+//
+// A script of pressed / released stretches is played into a `ScriptedButton`,
+// the manager is polled every `UI_POLL_INTERVAL_MS` of `MockClock` time as the
+// UI task polls it, and whatever reaches the `Recorder` is compared by name.
+
+use std::cell::{Cell, RefCell};
+
+use crate::config::*;
+use crate::events::UiEvent;
+use crate::input::{ButtonInput, EventSink, InputConfig, InputManager};
+use crate::ticks::MockClock;
+
+/// Down long enough to clear the debounce, well short of the hold ring.
+const CLICK_MS: u64 = 100;
+/// Up between the clicks of a double / triple click, inside the window.
+const GAP_MS: u64 = 150;
+
+/// Reads whatever level the script last set.
+struct ScriptedButton<'a>(&'a Cell<bool>);
+
+impl ButtonInput for ScriptedButton<'_> {
+    fn is_pressed(&mut self) -> bool {
+        self.0.get()
+    }
+}
+
+#[derive(Default)]
+struct Recorder(RefCell<Vec<UiEvent>>);
+
+impl EventSink for &Recorder {
+    fn send(&self, event: UiEvent) {
+        self.0.borrow_mut().push(event);
+    }
+}
+
+/// Play `script` — (pressed, for how many ms) — from `start_ms` on the clock
+/// and return the events sent.
+fn play(start_ms: u32, config: InputConfig, script: &[(bool, u64)]) -> Vec<UiEvent> {
+    let level = Cell::new(false);
+    let clock = MockClock::starting_at(start_ms);
+    let recorder = Recorder::default();
+    let mut input = InputManager::new(ScriptedButton(&level), &recorder, &clock, config);
+
+    for &(pressed, ms) in script {
+        level.set(pressed);
+        for _ in 0..ms / UI_POLL_INTERVAL_MS {
+            clock.advance(UI_POLL_INTERVAL_MS as u32);
+            input.update();
+        }
+    }
+    recorder.0.take()
+}
+
+/// `ButtonHoldProgress(0.5)` → "ButtonHoldProgress".
+fn names(events: &[UiEvent]) -> Vec<String> {
+    events
+        .iter()
+        .map(|e| format!("{:?}", e).split('(').next().unwrap().to_owned())
+        .collect()
+}
+
+/// Released long enough for the click window to lapse.
+fn settle() -> (bool, u64) {
+    (false, DOUBLE_CLICK_WINDOW_MS + 200)
+}
+
+#[test]
+fn single_click() {
+    let events = play(0, InputConfig::default(), &[(true, CLICK_MS), settle()]);
+    assert_eq!(names(&events), ["ButtonSingleClick"]);
+}
+
+#[test]
+fn single_click_is_held_back_until_the_window_lapses() {
+    let config = InputConfig::default();
+    let events = play(0, config, &[(true, CLICK_MS), (false, DOUBLE_CLICK_WINDOW_MS)]);
+    assert!(events.is_empty(), "{:?}", events);
+}
+
+#[test]
+fn contact_bounce_is_one_click() {
+    let bouncy = [(true, 10), (false, 10), (true, 20), (false, 10), (true, CLICK_MS)];
+    let mut script = bouncy.to_vec();
+    script.extend([(false, 10), (true, 10), settle()]);
+    let events = play(0, InputConfig::default(), &script);
+    assert_eq!(names(&events), ["ButtonSingleClick"]);
+}
+
+#[test]
+fn double_click() {
+    let script = [(true, CLICK_MS), (false, GAP_MS), (true, CLICK_MS), settle()];
+    let events = play(0, InputConfig::default(), &script);
+    assert_eq!(names(&events), ["ButtonDoubleClick"]);
+    assert!(!events.iter().any(|e| matches!(e, UiEvent::ButtonSingleClick)));
+}
+
+#[test]
+fn triple_click() {
+    let script = [
+        (true, CLICK_MS),
+        (false, GAP_MS),
+        (true, CLICK_MS),
+        (false, GAP_MS),
+        (true, CLICK_MS),
+        settle(),
+    ];
+    let events = play(0, InputConfig::default(), &script);
+    assert_eq!(names(&events), ["ButtonTripleClick"]);
+}
+
+#[test]
+fn clicks_further_apart_than_the_window_are_separate() {
+    let script = [(true, CLICK_MS), settle(), (true, CLICK_MS), settle()];
+    let events = play(0, InputConfig::default(), &script);
+    assert_eq!(names(&events), ["ButtonSingleClick", "ButtonSingleClick"]);
+}
+
+#[test]
+fn long_press() {
+    let script = [(true, LONG_PRESS_MS + 200), settle()];
+    let events = play(0, InputConfig::default(), &script);
+
+    // The ring fills from HOLD_PROGRESS_START_MS, one step per interval,
+    // ending on exactly 1.0; the long press itself comes on release.
+    let steps = (LONG_PRESS_MS - HOLD_PROGRESS_START_MS).div_ceil(HOLD_PROGRESS_INTERVAL_MS);
+    let mut expected = vec!["ButtonHoldProgress"; steps as usize + 1];
+    expected.push("ButtonLongPress");
+    assert_eq!(names(&events), expected);
+
+    let progress: Vec<f32> = events
+        .iter()
+        .filter_map(|e| match *e {
+            UiEvent::ButtonHoldProgress(p) => Some(p),
+            _ => None,
+        })
+        .collect();
+    let first = HOLD_PROGRESS_START_MS as f32 / LONG_PRESS_MS as f32;
+    assert!((progress[0] - first).abs() < 1e-6, "{:?}", progress);
+    assert!(progress.windows(2).all(|w| w[0] < w[1]), "{:?}", progress);
+    assert_eq!(progress.last(), Some(&1.0));
+}
+
+#[test]
+fn letting_go_after_the_ring_appears_cancels() {
+    let script = [(true, HOLD_PROGRESS_START_MS + 200), settle()];
+    let events = play(0, InputConfig::default(), &script);
+    let cancelled = names(&events);
+    assert_eq!(cancelled.last().map(String::as_str), Some("ButtonHoldCancelled"));
+    assert!(cancelled[..cancelled.len() - 1].iter().all(|n| n == "ButtonHoldProgress"));
+    assert!(cancelled.len() > 1, "{:?}", cancelled);
+}
+
+#[test]
+fn clicks_and_holds_across_the_clock_wrap() {
+    // Each script starts shortly before `now_ms()` wraps, so its timings straddle it.
+    let script = [(true, CLICK_MS), (false, GAP_MS), (true, CLICK_MS), settle()];
+    let events = play(u32::MAX - 150, InputConfig::default(), &script);
+    assert_eq!(names(&events), ["ButtonDoubleClick"]);
+
+    let events = play(u32::MAX - 30, InputConfig::default(), &[(true, CLICK_MS), settle()]);
+    assert_eq!(names(&events), ["ButtonSingleClick"]);
+
+    let script = [(true, LONG_PRESS_MS + 200), settle()];
+    let events = play(u32::MAX - 1000, InputConfig::default(), &script);
+    assert_eq!(names(&events).last().map(String::as_str), Some("ButtonLongPress"));
+}
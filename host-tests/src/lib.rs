@@ -0,0 +1,63 @@
+// PlastiWatch V2 — Host Tests
+//
+// The firmware crate only builds for the ESP32-C3, so the parts of it that
+// don't touch ESP-IDF are compiled a second time here, for the host, straight
+// from ../src via `#[path]`, and unit-tested:
+//
+//     cd host-tests && cargo test
+//
+// config.rs and events.rs are included as is, so the tests run against the
+// real timings, thresholds and events.  The few types they name from modules
+// that need the hardware (the IMU driver, the sample filter, the smoother)
+// are stood in for below; a variant config.rs uses and a stand-in lacks fails
+// the build rather than drifting.  The whole crate is `cfg(test)`.
+
+#![cfg(test)]
+// Lints the firmware build tolerates in these files.
+#![allow(clippy::derivable_impls)]
+
+#[path = "../../src/config.rs"]
+pub mod config;
+#[path = "../../src/decision.rs"]
+pub mod decision;
+#[path = "../../src/events.rs"]
+pub mod events;
+#[path = "../../src/i18n.rs"]
+pub mod i18n;
+#[path = "../../src/input.rs"]
+pub mod input;
+#[path = "../../src/ticks.rs"]
+pub mod ticks;
+
+pub mod drivers {
+    pub mod imu {
+        pub enum CycleRate {
+            Hz1_25,
+            Hz5,
+            Hz20,
+            Hz40,
+        }
+
+        #[derive(Debug, Clone, Copy, Default, PartialEq)]
+        pub struct CalibrationOffsets;
+    }
+}
+
+pub mod filter {
+    pub enum SampleFilter {
+        Off,
+        LowPass { cutoff_hz: f32 },
+        Median,
+    }
+}
+
+pub mod smoothing {
+    pub enum VotingPolicy {
+        Majority,
+        WeightedAverage,
+    }
+}
+
+mod decision_tests;
+mod input_tests;
+mod ticks_tests;
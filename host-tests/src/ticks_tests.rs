@@ -0,0 +1,71 @@
+// `ticks::MockClock`, the `Clock` trait's defaults, and interval arithmetic
+// across the u32 wrap of `now_ms()` (~49.7 days).
+
+use crate::config::DEEP_SLEEP_TIMEOUT_MS;
+use crate::ticks::{elapsed_ms, Clock, MockClock};
+
+#[test]
+fn mock_clock_only_moves_when_advanced() {
+    let clock = MockClock::starting_at(1000);
+    assert_eq!(clock.now_ms(), 1000);
+    assert_eq!(clock.now_ms(), 1000);
+    clock.advance(250);
+    assert_eq!(clock.now_ms(), 1250);
+    assert_eq!(clock.ms_since(1000), 250);
+}
+
+#[test]
+fn a_borrowed_mock_clock_reads_the_same_time() {
+    // The code under test can own `&clock` while the test keeps stepping it.
+    let clock = MockClock::default();
+    let borrowed = &clock;
+    clock.advance(40);
+    assert_eq!(borrowed.now_ms(), 40);
+    assert_eq!(Clock::ms_since(&borrowed, 15), 25);
+}
+
+#[test]
+fn elapsed_ms_is_right_across_the_wrap() {
+    assert_eq!(elapsed_ms(0x0000_0010, 0xFFFF_FFF0), 0x20);
+    assert_eq!(elapsed_ms(0, u32::MAX), 1);
+    assert_eq!(elapsed_ms(u32::MAX, u32::MAX), 0);
+    assert_eq!(elapsed_ms(u32::MAX, 0), u32::MAX);
+    // Either side of the wrap, the interval doesn't depend on where it starts.
+    for start in [0, 1000, u32::MAX - 999, u32::MAX - 1, u32::MAX] {
+        assert_eq!(elapsed_ms(start.wrapping_add(1500), start), 1500, "from {}", start);
+    }
+}
+
+#[test]
+fn ms_since_is_right_while_the_clock_steps_across_the_wrap() {
+    let clock = MockClock::starting_at(u32::MAX - 25);
+    let start = clock.now_ms();
+    for step in 1..=10 {
+        clock.advance(10);
+        assert_eq!(clock.ms_since(start), step * 10);
+    }
+    assert!(clock.now_ms() < start, "the clock should have wrapped");
+}
+
+/// The power task's inactivity check, as it reads the clock.
+fn inactive(clock: &MockClock, last_activity: u32) -> bool {
+    clock.ms_since(last_activity) > DEEP_SLEEP_TIMEOUT_MS
+}
+
+#[test]
+fn the_inactivity_timeout_holds_across_the_wrap() {
+    // Last activity a minute before the wrap: the timeout falls two minutes
+    // after it, where a plain `now - last` would underflow.
+    let clock = MockClock::starting_at(u32::MAX - 60_000);
+    let last_activity = clock.now_ms();
+
+    clock.advance(60_001);
+    assert!(clock.now_ms() < last_activity);
+    assert!(!inactive(&clock, last_activity));
+
+    clock.advance(DEEP_SLEEP_TIMEOUT_MS - 60_001);
+    assert!(!inactive(&clock, last_activity), "exactly the timeout is not past it");
+
+    clock.advance(1);
+    assert!(inactive(&clock, last_activity));
+}
@@ -0,0 +1,192 @@
+// PlastiWatch V2 — Battery State of Charge
+//
+// LiPo discharge is far from linear: the cell spends most of its runtime on
+// a plateau around 3.7–3.9 V and then drops off steeply.  A straight line
+// between 3.3 V and 4.2 V therefore reads ~50 % when the cell is nearly
+// full-minus-a-bit and collapses suddenly at the end.  Instead, the voltage
+// is mapped through a typical single-cell LiPo discharge curve (resting
+// voltage, light load) with linear interpolation between points.
+//
+// The ADC sees the battery through a resistor divider whose ratio varies by
+// board revision.  `BATTERY_DIVIDER_RATIO` sets it; alternatively power the
+// board from a known voltage with `BATTERY_CALIBRATION_VOLTAGE` set and the
+// power task back-computes the effective ratio on its first reading, uses it
+// for the rest of the session and logs the value to put in config.
+//
+// `ChargeDetector` infers USB power from the (smoothed) voltage alone: either
+// it exceeds what a cell on its own can reach, or it keeps rising check after
+// check.  Charging ends only once the voltage falls clearly below the peak
+// seen while charging, so a reading hovering at the threshold doesn't flap.
+//
+// `Gauge` turns the per-check percentage into the one that is reported, so
+// the number on screen doesn't bounce: it moves at most
+// `BATTERY_GAUGE_MAX_STEP_PCT` per check and only goes up while charging.
+// The first reading after boot is taken as is.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use anyhow::bail;
+
+use crate::config::*;
+
+/// Calibrated divider ratio as `f32` bits; 0 = not calibrated.
+static CALIBRATED_RATIO: AtomicU32 = AtomicU32::new(0);
+
+/// `(volts, percent)` points, ascending in voltage.
+#[rustfmt::skip]
+const DISCHARGE_CURVE: [(f32, f32); 21] = [
+    (3.27,   0.0),
+    (3.61,   5.0),
+    (3.69,  10.0),
+    (3.71,  15.0),
+    (3.73,  20.0),
+    (3.75,  25.0),
+    (3.77,  30.0),
+    (3.79,  35.0),
+    (3.80,  40.0),
+    (3.82,  45.0),
+    (3.84,  50.0),
+    (3.85,  55.0),
+    (3.87,  60.0),
+    (3.91,  65.0),
+    (3.95,  70.0),
+    (3.98,  75.0),
+    (4.02,  80.0),
+    (4.08,  85.0),
+    (4.11,  90.0),
+    (4.15,  95.0),
+    (4.20, 100.0),
+];
+
+/// Remaining charge (0.0–100.0 %) for a cell voltage.  Clamps below the
+/// first and above the last point of the curve.
+pub fn percent_from_voltage(voltage: f32) -> f32 {
+    let (v_min, p_min) = DISCHARGE_CURVE[0];
+    let (v_max, p_max) = DISCHARGE_CURVE[DISCHARGE_CURVE.len() - 1];
+    if voltage <= v_min {
+        return p_min;
+    }
+    if voltage >= v_max {
+        return p_max;
+    }
+
+    for pair in DISCHARGE_CURVE.windows(2) {
+        let (v0, p0) = pair[0];
+        let (v1, p1) = pair[1];
+        if voltage <= v1 {
+            return p0 + (voltage - v0) / (v1 - v0) * (p1 - p0);
+        }
+    }
+    p_max
+}
+
+/// Battery volts per ADC-pin volt: the calibrated ratio if one has been
+/// measured this session, otherwise `BATTERY_DIVIDER_RATIO`.
+pub fn divider_ratio() -> f32 {
+    match CALIBRATED_RATIO.load(Ordering::Relaxed) {
+        0 => BATTERY_DIVIDER_RATIO,
+        bits => f32::from_bits(bits),
+    }
+}
+
+/// Whether `calibrate_divider` has succeeded this session.
+pub fn divider_calibrated() -> bool {
+    CALIBRATED_RATIO.load(Ordering::Relaxed) != 0
+}
+
+/// Back-compute the divider ratio from the ADC pin voltage measured while
+/// the battery input sits at `known_voltage`, and use it from now on.
+pub fn calibrate_divider(pin_voltage: f32, known_voltage: f32) -> anyhow::Result<f32> {
+    if pin_voltage < 0.1 {
+        bail!("ADC pin reads {:.3} V — is the battery connected?", pin_voltage);
+    }
+    let ratio = known_voltage / pin_voltage;
+    if !(0.5..=5.0).contains(&ratio) {
+        bail!("implausible divider ratio {:.3}", ratio);
+    }
+
+    CALIBRATED_RATIO.store(ratio.to_bits(), Ordering::Relaxed);
+    log::info!(
+        "Battery divider calibrated: {:.4} (set BATTERY_DIVIDER_RATIO to keep it)",
+        ratio
+    );
+    Ok(ratio)
+}
+
+/// Charging state inferred from successive battery voltage readings.
+pub struct ChargeDetector {
+    charging: bool,
+    last_voltage: Option<f32>,
+    rising_checks: u32,
+    /// Highest voltage seen since charging started.
+    peak: f32,
+}
+
+impl ChargeDetector {
+    pub fn new() -> Self {
+        Self {
+            charging: false,
+            last_voltage: None,
+            rising_checks: 0,
+            peak: 0.0,
+        }
+    }
+
+    pub fn charging(&self) -> bool {
+        self.charging
+    }
+
+    /// Feed one smoothed voltage reading.  Returns the new state when it
+    /// changes (`Some(true)` = started charging, `Some(false)` = stopped).
+    pub fn update(&mut self, voltage: f32) -> Option<bool> {
+        if let Some(last) = self.last_voltage {
+            if voltage > last + CHARGING_TREND_STEP_V {
+                self.rising_checks += 1;
+            } else if voltage < last - CHARGING_TREND_STEP_V {
+                self.rising_checks = 0;
+            }
+        }
+        self.last_voltage = Some(voltage);
+
+        if !self.charging {
+            if voltage >= CHARGING_START_V || self.rising_checks >= CHARGING_TREND_CHECKS {
+                self.charging = true;
+                self.peak = voltage;
+                return Some(true);
+            }
+        } else {
+            self.peak = self.peak.max(voltage);
+            if voltage < CHARGING_START_V && voltage < self.peak - CHARGING_STOP_DROP_V {
+                self.charging = false;
+                self.rising_checks = 0;
+                return Some(false);
+            }
+        }
+        None
+    }
+}
+
+/// Slew-limited, discharge-monotonic battery percentage.
+pub struct Gauge {
+    shown: Option<f32>,
+}
+
+impl Gauge {
+    pub fn new() -> Self {
+        Self { shown: None }
+    }
+
+    /// Feed one computed level; returns the level to report.
+    pub fn update(&mut self, level: f32, charging: bool) -> f32 {
+        let shown = match self.shown {
+            None => level,
+            Some(prev) => {
+                let target = if charging { level } else { level.min(prev) };
+                let step = BATTERY_GAUGE_MAX_STEP_PCT;
+                prev + (target - prev).clamp(-step, step)
+            }
+        };
+        self.shown = Some(shown);
+        shown
+    }
+}
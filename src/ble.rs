@@ -0,0 +1,141 @@
+// PlastiWatch V2 — BLE Status Service (feature = "ble")
+//
+// Advertises as "PlastiWatch" and exposes the live stats as readable,
+// notifiable GATT characteristics so a phone app can display them:
+//
+//   Battery Service (0x180F) / Battery Level (0x2A19)   u8, percent
+//   PlastiWatch service  …f001  activity                [class index, confidence %]
+//                        …f002  steps                   u32 little-endian
+//                        …f003  battery voltage         u16 little-endian, mV
+//
+// The class index follows `ActivityClass::index` (4 = unknown).
+//
+// The NimBLE host runs its own FreeRTOS task.  `init` sets the service up
+// once at boot, seeding each characteristic from `state::status` so a
+// central that connects before the first change reads real values; a small
+// "ble" thread in `main` then subscribes to the UI event broadcast and hands
+// each event to `publish`, which updates (and notifies) the matching
+// characteristic.  The UI task calls `pause` to stop advertising before deep
+// sleep.
+//
+// Needs Bluetooth in the ESP-IDF config — build with
+// `ESP_IDF_SDKCONFIG_DEFAULTS="sdkconfig.defaults;sdkconfig.defaults.ble"`.
+
+use std::sync::{Arc, OnceLock};
+
+use esp32_nimble::utilities::mutex::Mutex;
+use esp32_nimble::{
+    uuid128, BLEAdvertisementData, BLECharacteristic, BLEDevice, BleUuid, NimbleProperties,
+};
+
+use crate::events::{ActivityClass, UiEvent};
+use crate::state::{self, SharedState};
+
+const DEVICE_NAME: &str = "PlastiWatch";
+
+const BATTERY_SERVICE: BleUuid = BleUuid::from_uuid16(0x180F);
+const BATTERY_LEVEL: BleUuid = BleUuid::from_uuid16(0x2A19);
+const STATUS_SERVICE: BleUuid = uuid128!("5a1e0000-7c2b-4f3e-9d61-70b1a5f0f000");
+const ACTIVITY_CHAR: BleUuid = uuid128!("5a1e0000-7c2b-4f3e-9d61-70b1a5f0f001");
+const STEPS_CHAR: BleUuid = uuid128!("5a1e0000-7c2b-4f3e-9d61-70b1a5f0f002");
+const VOLTAGE_CHAR: BleUuid = uuid128!("5a1e0000-7c2b-4f3e-9d61-70b1a5f0f003");
+
+struct Characteristics {
+    battery: Arc<Mutex<BLECharacteristic>>,
+    activity: Arc<Mutex<BLECharacteristic>>,
+    steps: Arc<Mutex<BLECharacteristic>>,
+    voltage: Arc<Mutex<BLECharacteristic>>,
+}
+
+static CHARACTERISTICS: OnceLock<Characteristics> = OnceLock::new();
+
+/// Create the GATT services and start advertising.  Call once at boot.
+pub fn init(state: &SharedState) -> anyhow::Result<()> {
+    let device = BLEDevice::take();
+    BLEDevice::set_device_name(DEVICE_NAME).map_err(|e| anyhow::anyhow!("BLE name: {:?}", e))?;
+
+    let server = device.get_server();
+    server.on_connect(|_, desc| log::info!("BLE: central connected ({:?})", desc.address()));
+    server.on_disconnect(|_, _| log::info!("BLE: central disconnected"));
+    // Advertising resumes on its own after a disconnect.
+    server.advertise_on_disconnect(true);
+
+    let read_notify = NimbleProperties::READ | NimbleProperties::NOTIFY;
+    let battery_service = server.create_service(BATTERY_SERVICE);
+    let battery = battery_service.lock().create_characteristic(BATTERY_LEVEL, read_notify);
+    let status_service = server.create_service(STATUS_SERVICE);
+    let activity = status_service.lock().create_characteristic(ACTIVITY_CHAR, read_notify);
+    let steps = status_service.lock().create_characteristic(STEPS_CHAR, read_notify);
+    let voltage = status_service.lock().create_characteristic(VOLTAGE_CHAR, read_notify);
+
+    let chars = Characteristics {
+        battery,
+        activity,
+        steps,
+        voltage,
+    };
+    let status = state::status(state);
+    chars.set_activity(status.activity, status.confidence);
+    chars.set_steps(status.steps);
+    if let (Some(pct), Some(volts)) = (status.battery_pct, status.battery_v) {
+        chars.set_battery(pct, volts);
+    }
+    let _ = CHARACTERISTICS.set(chars);
+
+    let mut advertising = device.get_advertising().lock();
+    advertising
+        .set_data(
+            BLEAdvertisementData::new()
+                .name(DEVICE_NAME)
+                .add_service_uuid(STATUS_SERVICE),
+        )
+        .map_err(|e| anyhow::anyhow!("BLE advertising data: {:?}", e))?;
+    advertising
+        .start()
+        .map_err(|e| anyhow::anyhow!("BLE advertising: {:?}", e))?;
+
+    log::info!("BLE: advertising as \"{}\"", DEVICE_NAME);
+    Ok(())
+}
+
+/// Mirror a UI event onto the GATT characteristics (other events are
+/// ignored).  No-op if `init` failed or was never called.
+pub fn publish(event: &UiEvent) {
+    let Some(chars) = CHARACTERISTICS.get() else {
+        return;
+    };
+
+    match *event {
+        UiEvent::UpdateActivity(activity, confidence) => chars.set_activity(activity, confidence),
+        UiEvent::UpdateBattery { pct, volts } => chars.set_battery(pct, volts),
+        UiEvent::UpdateSteps(steps) => chars.set_steps(steps),
+        _ => {}
+    }
+}
+
+impl Characteristics {
+    fn set_activity(&self, activity: ActivityClass, confidence: f32) {
+        let pct = (confidence.clamp(0.0, 1.0) * 100.0).round() as u8;
+        self.activity.lock().set_value(&[activity.index() as u8, pct]).notify();
+    }
+
+    fn set_battery(&self, pct: f32, volts: f32) {
+        self.battery.lock().set_value(&[pct.clamp(0.0, 100.0) as u8]).notify();
+        let mv = (volts * 1000.0).round().clamp(0.0, u16::MAX as f32) as u16;
+        self.voltage.lock().set_value(&mv.to_le_bytes()).notify();
+    }
+
+    fn set_steps(&self, steps: u32) {
+        self.steps.lock().set_value(&steps.to_le_bytes()).notify();
+    }
+}
+
+/// Stop advertising ahead of deep sleep.
+pub fn pause() {
+    if CHARACTERISTICS.get().is_none() {
+        return;
+    }
+    if let Err(e) = BLEDevice::take().get_advertising().lock().stop() {
+        log::warn!("BLE: failed to stop advertising ({:?})", e);
+    }
+}
@@ -0,0 +1,60 @@
+// PlastiWatch V2 — Event Fan-Out
+//
+// `mpsc` channels have exactly one receiver, so every new consumer of UI
+// events (BLE, telemetry, a logger) would otherwise need its own `send` at
+// each producer.  `Broadcast` is a cloneable sender that delivers every
+// event to all current subscribers instead; each subscriber gets an
+// ordinary `Receiver`, so consumers (like the UI task's `try_recv` loop)
+// don't change.
+//
+// Subscribe before handing out senders so no early event is missed.  A
+// subscriber whose receiver has been dropped is pruned on the next send.
+
+use std::sync::mpsc::{self, Receiver, SendError, Sender};
+use std::sync::{Arc, Mutex};
+
+pub struct Broadcast<T> {
+    subscribers: Arc<Mutex<Vec<Sender<T>>>>,
+}
+
+// Manual impl: cloning the handle doesn't need `T: Clone`.
+impl<T> Clone for Broadcast<T> {
+    fn clone(&self) -> Self {
+        Self {
+            subscribers: Arc::clone(&self.subscribers),
+        }
+    }
+}
+
+impl<T: Clone> Default for Broadcast<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone> Broadcast<T> {
+    pub fn new() -> Self {
+        Self {
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Receive every event sent from now on.
+    pub fn subscribe(&self) -> Receiver<T> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribers.lock().unwrap().push(tx);
+        rx
+    }
+
+    /// Deliver `event` to every live subscriber.  Fails (returning the event)
+    /// only when nobody is listening any more.
+    pub fn send(&self, event: T) -> Result<(), SendError<T>> {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.retain(|tx| tx.send(event.clone()).is_ok());
+        if subscribers.is_empty() {
+            Err(SendError(event))
+        } else {
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,76 @@
+// PlastiWatch V2 — Training Data Capture (feature = "capture")
+//
+// Streams timestamped 6-axis IMU samples over the serial console, at the
+// true sample rate, so new training data can be collected from this exact
+// hardware.  Start and stop it with a double-click or the serial `capture`
+// command; classification pauses while a recording is running.
+//
+// Output format matches the Edge Impulse CSV Wizard: a header line followed
+// by one row per sample, with the timestamp in milliseconds since the
+// recording started.  Drop the gyro columns in the wizard for an
+// accelerometer-only impulse.
+//
+//   timestamp,accX,accY,accZ,gyrX,gyrY,gyrZ
+//   0,0.0123,-0.0456,0.9987,0.31,-0.12,0.05
+//   16,0.0130,-0.0449,0.9991,0.28,-0.10,0.07
+//
+// Save the console output between the header and the "capture stopped" log
+// line to a `.csv` file and upload it through Data acquisition → CSV Wizard.
+// Lower the log level (or ignore `I (…)` lines) while capturing so log output
+// doesn't interleave with samples.
+//
+// State lives in module-level atomics so the sensor task (producer) and the
+// UI task (start/stop + counter display) can share it without threading extra
+// handles through `main` for a feature-gated build.
+
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use crate::events::SensorData;
+use crate::ticks;
+
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static SAMPLE_COUNT: AtomicU32 = AtomicU32::new(0);
+static START_MS: AtomicU32 = AtomicU32::new(0);
+
+/// Begin a new recording: reset the counter and print the CSV header.
+pub fn start() {
+    SAMPLE_COUNT.store(0, Ordering::Relaxed);
+    START_MS.store(crate::now_ms(), Ordering::Relaxed);
+    println!("timestamp,accX,accY,accZ,gyrX,gyrY,gyrZ");
+    ACTIVE.store(true, Ordering::SeqCst);
+    log::info!("Capture started");
+}
+
+/// End the current recording.
+pub fn stop() {
+    ACTIVE.store(false, Ordering::SeqCst);
+    log::info!(
+        "Capture stopped — {} samples",
+        SAMPLE_COUNT.load(Ordering::Relaxed)
+    );
+}
+
+/// Whether a recording is in progress.
+pub fn is_active() -> bool {
+    ACTIVE.load(Ordering::SeqCst)
+}
+
+/// Number of samples emitted since the last `start()`.
+pub fn sample_count() -> u32 {
+    SAMPLE_COUNT.load(Ordering::Relaxed)
+}
+
+/// Emit one CSV row if a recording is in progress.  Called by the sensor task
+/// for every successful IMU read.
+pub fn record(data: &SensorData) {
+    if !is_active() {
+        return;
+    }
+
+    let t = ticks::elapsed_ms(data.timestamp_ms, START_MS.load(Ordering::Relaxed));
+    println!(
+        "{},{:.4},{:.4},{:.4},{:.2},{:.2},{:.2}",
+        t, data.ax, data.ay, data.az, data.gx, data.gy, data.gz
+    );
+    SAMPLE_COUNT.fetch_add(1, Ordering::Relaxed);
+}
@@ -0,0 +1,123 @@
+// PlastiWatch V2 — Wall-Clock Time
+//
+// Time of day is kept by the ESP-IDF system clock (`gettimeofday`), which is
+// driven by the RTC timer and keeps counting through deep sleep.  It is lost
+// on a power cycle, so with `CLOCK_USE_DS3231` a battery-backed DS3231 on the
+// I2C bus restores it at cold boot and is updated whenever the time is set.
+//
+// Until the time has been set (serial `time HH:MM`, see the console task)
+// `now()` returns `None` and the clock screen shows "--:--".  The display is
+// local time: UTC shifted by `CLOCK_UTC_OFFSET_MIN`.
+
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::*;
+use crate::drivers::imu::SharedBus;
+use crate::drivers::rtc::{self, Ds3231};
+
+/// Anything before 2024-01-01 means the clock was never set.
+const MIN_VALID_UNIX: i64 = 1_704_067_200;
+
+/// The external RTC, when enabled and found at boot.
+static RTC: OnceLock<Ds3231> = OnceLock::new();
+
+/// Local time of day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeOfDay {
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+/// Probe for the DS3231 (if enabled) and, if the system clock was reset by a
+/// power cycle, load the time from it.  Call once at boot.
+pub fn init(bus: SharedBus) {
+    if CLOCK_USE_DS3231 {
+        let rtc = Ds3231::new(bus);
+        if rtc.is_connected() {
+            log::info!("DS3231 RTC found");
+            let _ = RTC.set(rtc);
+        } else {
+            log::warn!("DS3231 RTC not found — time will not survive power loss");
+        }
+    }
+
+    if unix_now().is_some() {
+        log::info!("Clock kept through sleep");
+        return;
+    }
+    if let Some(rtc) = RTC.get() {
+        match rtc.read_unix() {
+            Ok(unix) if unix >= MIN_VALID_UNIX => {
+                set_system_time(unix);
+                log::info!("Clock restored from DS3231");
+            }
+            Ok(_) => log::warn!("DS3231 holds no valid time"),
+            Err(e) => log::warn!("DS3231 read failed: {}", e),
+        }
+    }
+}
+
+/// `unix` as local "YYYY-MM-DD HH:MM".
+pub fn format_local(unix: i64) -> String {
+    let local = unix + CLOCK_UTC_OFFSET_MIN as i64 * 60;
+    let (year, month, day) = rtc::civil_from_days(local.div_euclid(86_400));
+    let secs = local.rem_euclid(86_400);
+    format!("{}-{:02}-{:02} {:02}:{:02}", year, month, day, secs / 3600, secs / 60 % 60)
+}
+
+/// Current local time of day, or `None` if the clock has not been set.
+pub fn now() -> Option<TimeOfDay> {
+    let local = unix_now()? + CLOCK_UTC_OFFSET_MIN as i64 * 60;
+    let secs = local.rem_euclid(86_400);
+    Some(TimeOfDay {
+        hour: (secs / 3600) as u8,
+        minute: (secs / 60 % 60) as u8,
+        second: (secs % 60) as u8,
+    })
+}
+
+/// Local day number (days since 1970-01-01, local time), or `None` if the
+/// clock has not been set.
+pub fn local_day() -> Option<i64> {
+    let local = unix_now()? + CLOCK_UTC_OFFSET_MIN as i64 * 60;
+    Some(local.div_euclid(86_400))
+}
+
+/// Set the local time of day, keeping the current date (or 2024-01-01 if
+/// the clock was never set).  Also updates the DS3231 when present.
+pub fn set_time(hour: u8, minute: u8, second: u8) -> anyhow::Result<()> {
+    anyhow::ensure!(hour < 24 && minute < 60 && second < 60, "invalid time");
+
+    let offset = CLOCK_UTC_OFFSET_MIN as i64 * 60;
+    let local_midnight = match unix_now() {
+        Some(unix) => (unix + offset).div_euclid(86_400) * 86_400,
+        None => MIN_VALID_UNIX,
+    };
+    let unix = local_midnight - offset + hour as i64 * 3600 + minute as i64 * 60 + second as i64;
+
+    set_system_time(unix);
+    if let Some(rtc) = RTC.get() {
+        rtc.write_unix(unix)?;
+    }
+    log::info!("Clock set to {:02}:{:02}:{:02}", hour, minute, second);
+    Ok(())
+}
+
+/// Current Unix time (UTC seconds), or `None` if the clock has not been set.
+pub fn unix_now() -> Option<i64> {
+    let unix = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs() as i64;
+    (unix >= MIN_VALID_UNIX).then_some(unix)
+}
+
+fn set_system_time(unix: i64) {
+    let tv = esp_idf_sys::timeval {
+        tv_sec: unix as esp_idf_sys::time_t,
+        tv_usec: 0,
+    };
+    unsafe {
+        esp_idf_sys::settimeofday(&tv, core::ptr::null());
+    }
+}
+
@@ -1,6 +1,11 @@
 // PlastiWatch V2 — Hardware & System Configuration
 // Target: Seeed Studio Xiao ESP32-C3 (RISC-V)
 
+use crate::drivers::imu::CycleRate;
+use crate::filter::SampleFilter;
+use crate::i18n::Language;
+use crate::smoothing::VotingPolicy;
+
 // ---------------------------------------------------------------------------
 // GPIO Pin Definitions (Xiao ESP32-C3 pinout)
 // ---------------------------------------------------------------------------
@@ -9,13 +14,39 @@ pub const PIN_HAPTIC: i32 = 4;      // D2/A2 — Haptic motor control
 pub const PIN_I2C_SDA: i32 = 6;     // D4    — I2C data line
 pub const PIN_I2C_SCL: i32 = 7;     // D5    — I2C clock line
 pub const PIN_BATTERY_ADC: u32 = 2; // D0/A0 — Battery voltage (ADC)
+pub const PIN_IMU_INT: i32 = 5;     // D3    — MPU6050 INT (deep-sleep wake needs GPIO0–5)
+
+// ---------------------------------------------------------------------------
+// Battery Sensing
+// ---------------------------------------------------------------------------
+pub const BATTERY_DIVIDER_RATIO: f32 = 2.0;             // Battery V per ADC-pin V (1:2 divider)
+// Set to the supply voltage (e.g. Some(4.00)) to measure the ratio at boot.
+pub const BATTERY_CALIBRATION_VOLTAGE: Option<f32> = None;
+pub const BATTERY_LOW_PCT: f32 = 15.0;                  // Indicator switches to "low" below this
+pub const BATTERY_GAUGE_MAX_STEP_PCT: f32 = 1.0;        // Most the shown percent moves per check
+pub const CHARGING_START_V: f32 = 4.25;                 // Only reachable on USB power
+pub const CHARGING_STOP_DROP_V: f32 = 0.08;             // Drop from charging peak = unplugged
+pub const CHARGING_TREND_STEP_V: f32 = 0.005;           // Per-check rise that counts as "rising"
+pub const CHARGING_TREND_CHECKS: u32 = 6;               // Consecutive rises (1 min) = charging
 
 // ---------------------------------------------------------------------------
 // I2C Bus
 // ---------------------------------------------------------------------------
-pub const I2C_ADDR_MPU6050: u8 = 0x68;
+// The DS3231 is fixed at 0x68, the MPU6050's default: with the RTC fitted the
+// IMU's AD0 pin must be strapped high, which moves it to 0x69.
+pub const I2C_ADDR_MPU6050: u8 = if CLOCK_USE_DS3231 { 0x69 } else { 0x68 };
 pub const I2C_ADDR_OLED: u8 = 0x3C;
-pub const I2C_TIMEOUT_TICKS: u32 = 1000; // FreeRTOS ticks
+pub const I2C_ADDR_DS3231: u8 = 0x68;
+const _: () = assert!(I2C_ADDR_DS3231 != I2C_ADDR_MPU6050 || !CLOCK_USE_DS3231);
+pub const I2C_BAUD_HZ: u32 = 400_000;          // Fast-mode; drop to 100_000 for long leads
+pub const I2C_TIMEOUT_TICKS_OLED: u32 = 1000;  // FreeRTOS ticks per display transaction
+pub const I2C_TIMEOUT_TICKS_IMU: u32 = 1000;   // FreeRTOS ticks per IMU transaction
+pub const I2C_TIMEOUT_TICKS_RTC: u32 = 100;    // FreeRTOS ticks per DS3231 transaction
+pub const I2C_SCAN_TIMEOUT_TICKS: u32 = 10;    // Per-address probe (boot scan, IMU presence check)
+pub const I2C_MAX_RETRIES: u32 = 3;            // Extra attempts after a failed IMU read
+pub const I2C_RETRY_DELAY_MS: u64 = 2;         // Backoff step (2, 4, 6 ms …)
+pub const SELF_TEST_ATTEMPTS: u32 = 3;         // Boot probes per device before "FAILED"
+pub const SELF_TEST_RETRY_DELAY_MS: u64 = 20;  // Pause between boot probes
 
 // ---------------------------------------------------------------------------
 // Display (SSD1306 OLED)
@@ -23,40 +54,231 @@ pub const I2C_TIMEOUT_TICKS: u32 = 1000; // FreeRTOS ticks
 pub const SCREEN_WIDTH: u32 = 128;
 pub const SCREEN_HEIGHT: u32 = 64;
 pub const DISPLAY_BUFFER_SIZE: usize = (SCREEN_WIDTH as usize * SCREEN_HEIGHT as usize) / 8; // 1024
+pub const OLED_DEFAULT_BRIGHTNESS: u8 = 0xCF;  // SSD1306 contrast (0–255)
+pub const OLED_DIM_BRIGHTNESS: u8 = 0x08;      // Contrast while dimmed
+pub const SCROLL_GAP_PX: i32 = 40;             // Blank run between marquee repeats
+pub const TEXT_BLOCK_MARGIN_PX: u32 = 2;       // Left/right inset of `draw_text_block`
+pub const TEXT_BLOCK_LINE_GAP_PX: u32 = 2;     // Blank rows between its lines
+pub const UI_LANGUAGE: Language = Language::English; // On-screen strings (`i18n`)
+
+// ---------------------------------------------------------------------------
+// Haptic Motor (LEDC PWM)
+// ---------------------------------------------------------------------------
+pub const HAPTIC_PWM_FREQ_HZ: u32 = 20_000;   // Above audible range — no motor whine
+pub const HAPTIC_INTENSITY_MEDIUM: u8 = 160;  // Click confirmation
+pub const HAPTIC_INTENSITY_STRONG: u8 = 255;  // Alerts
+// Softer haptics on a low battery (not while charging), where a long strong
+// buzz can sag the rail far enough to brown out the MCU.
+pub const HAPTIC_LOW_BATTERY_PCT: f32 = BATTERY_LOW_PCT; // Limits apply below this
+pub const HAPTIC_LOW_BATTERY_INTENSITY: u8 = 120;        // Strength cap
+pub const HAPTIC_LOW_BATTERY_MAX_ON_MS: u64 = 200;       // Longest single pulse (dash > dot)
+// Heartbeat pulse when the reported activity changes to one of these, in
+// `ActivityClass::index` order: idle, snake (has its own alert), updown
+// (walking), wave (running), unknown.
+pub const HAPTIC_ON_ACTIVITY: [bool; EI_LABEL_COUNT + 1] = [false, false, true, true, false];
 
 // ---------------------------------------------------------------------------
 // Task Stack Sizes (bytes)
 // ---------------------------------------------------------------------------
 pub const STACK_SENSOR: usize = 4096;
-pub const STACK_AI: usize = 8192;
+pub const STACK_AI: usize = 12288;  // Feature window + normalised copy (6-axis: 2 × 3 KB)
 pub const STACK_UI: usize = 8192;
 pub const STACK_POWER: usize = 4096;
+pub const STACK_HAPTIC: usize = 2048;
+pub const STACK_CONSOLE: usize = 4096;
+#[cfg(feature = "ble")]
+pub const STACK_BLE: usize = 4096;
 
 // ---------------------------------------------------------------------------
 // Timing (milliseconds)
 // ---------------------------------------------------------------------------
 pub const SENSOR_SAMPLE_INTERVAL_MS: u64 = 16;        // ~62.5 Hz
-pub const UI_POLL_INTERVAL_MS: u64 = 10;               // 100 Hz input poll / refresh
+pub const SENSOR_IDLE_SAMPLE_INTERVAL_MS: u64 = 100;   // 10 Hz after sustained idle
+pub const SENSOR_IDLE_LOW_POWER: bool = true;          // MPU6050 cycle mode (no gyro) while idle
+pub const SENSOR_IDLE_CYCLE_RATE: CycleRate = CycleRate::Hz20; // Keep ≥ the idle sample rate
+pub const SENSOR_REINIT_AFTER_ERRORS: u32 = 10;        // Consecutive read errors → re-init the IMU
+pub const SENSOR_MAX_REINIT_ATTEMPTS: u32 = 5;         // Failed re-inits in a row before giving up
+pub const SENSOR_HEALTH_LOG_INTERVAL_MS: u64 = 60_000; // Error summary cadence (only if errors grew)
+pub const SENSOR_LOST_TIMEOUT_MS: u64 = 5000;          // No sample this long → "sensor lost" (covers calibration)
+pub const SENSOR_LOST_RESTART_MS: u64 = 30_000;        // Still none → restart the chip
+pub const SENSOR_PROBE_INTERVAL_MS: u64 = 1000;        // IMU presence check (WHO_AM_I), also while unplugged
+pub const SENSOR_DISCONNECTED_AFTER_MS: u64 = 3000;    // Unanswered this long → "no IMU" on screen
+const _: () = assert!(SENSOR_DISCONNECTED_AFTER_MS >= SENSOR_PROBE_INTERVAL_MS);
+// Per-axis smoothing of every sample (`filter`); the model sees the output, so
+// train on captures taken with the same setting.
+pub const SENSOR_FILTER: SampleFilter = SampleFilter::Off;
+pub const SENSOR_MEDIAN_WINDOW: usize = 3;             // Samples per moving median (odd)
+const _: () = assert!(SENSOR_MEDIAN_WINDOW % 2 == 1);
+const _: () = assert!(match SENSOR_FILTER {
+    // Cutoff must sit below the Nyquist frequency of the full sample rate.
+    SampleFilter::LowPass { cutoff_hz } => {
+        cutoff_hz > 0.0 && cutoff_hz < 500.0 / SENSOR_SAMPLE_INTERVAL_MS as f32
+    }
+    _ => true,
+});
+const _: () = assert!(SENSOR_LOST_TIMEOUT_MS > 10 * SENSOR_IDLE_SAMPLE_INTERVAL_MS);
+pub const UI_POLL_INTERVAL_MS: u64 = 10;               // 100 Hz input poll
+pub const UI_REFRESH_INTERVAL_MS: u64 = 40;            // OLED frames at most 25 Hz (UI task)
+pub const UI_DIM_POLL_INTERVAL_MS: u64 = 40;           // 25 Hz while dimmed (still ≥ debounce)
 pub const BATTERY_CHECK_INTERVAL_MS: u64 = 10_000;     // 10 seconds
+pub const WATCHDOG_TIMEOUT_MS: u32 = 30_000;           // A watched task silent this long → reset
+const _: () = assert!(WATCHDOG_TIMEOUT_MS as u64 > 2 * BATTERY_CHECK_INTERVAL_MS);
+pub const BATTERY_ADC_SAMPLES: usize = 9;              // Median-of-N per check
+pub const BATTERY_ADC_SAMPLE_GAP_MS: u64 = 2;          // Spacing between ADC reads
+pub const BATTERY_EMA_ALPHA: f32 = 0.3;                // Smoothing across checks
 pub const DEBOUNCE_MS: u64 = 50;
+pub const HAPTIC_CLICK_MS: u64 = 50;                   // Click feedback pulse
 pub const LONG_PRESS_MS: u64 = 3000;                   // 3-second hold
+pub const HOLD_PROGRESS_START_MS: u64 = 500;           // Power-off ring appears after this
+pub const HOLD_PROGRESS_INTERVAL_MS: u64 = 100;        // Ring update rate
+pub const BUTTON_REPEAT_DELAY_MS: u64 = 600;           // Hold before auto-repeat starts
+pub const BUTTON_REPEAT_INTERVAL_MS: u64 = 150;        // Auto-repeat rate
 pub const DOUBLE_CLICK_WINDOW_MS: u64 = 400;
+pub const BUTTON_STUCK_MS: u64 = 10_000;               // Held this long = stuck-button fault
+const _: () = assert!(BUTTON_STUCK_MS > LONG_PRESS_MS);
 pub const BOOT_HOLD_MS: u64 = 3000;                    // 3-second boot trigger
-pub const INACTIVITY_TIMEOUT_MS: u32 = 180_000;        // 3 minutes → sleep
+// Power-saving stages, all counted from the last activity (button, wrist
+// raise or movement); any new activity resets them together.
+pub const DIM_TIMEOUT_MS: u32 = 20_000;                // 20 s → dim the OLED
+pub const SCREEN_OFF_TIMEOUT_MS: u32 = 60_000;         // 1 minute → OLED panel off
+pub const DEEP_SLEEP_TIMEOUT_MS: u32 = 180_000;        // 3 minutes → deep sleep
+const _: () = assert!(DIM_TIMEOUT_MS <= SCREEN_OFF_TIMEOUT_MS);
+const _: () = assert!(SCREEN_OFF_TIMEOUT_MS <= DEEP_SLEEP_TIMEOUT_MS);
 pub const BOOT_LOGO_DISPLAY_MS: u64 = 1000;            // Logo splash duration
-pub const BOOT_TEXT_DISPLAY_MS: u64 = 1000;             // Text splash duration
+pub const BOOT_TEXT_DISPLAY_MS: u64 = 1000;            // Text splash duration
+pub const BOOT_STATUS_DISPLAY_MS: u64 = 1000;          // Self-test result duration
+pub const BOOT_SPLASH_SKIP_ON_PRESS: bool = true;      // A press advances to the next boot screen
+pub const SLEEP_HAPTIC_GRACE_MS: u64 = 600;            // Let a confirmation buzz finish first
+pub const SLEEP_PREP_TIMEOUT_MS: u64 = 1500;           // Max wait for the UI to power down
+pub const STATS_REFRESH_INTERVAL_MS: u64 = 1000;       // Debug/stats screen redraw
+pub const CLOCK_REFRESH_INTERVAL_MS: u64 = 1000;       // Clock screen redraw
+pub const SCROLL_STEP_INTERVAL_MS: u64 = 30;           // One marquee column per step (~33 px/s)
+pub const CONSOLE_POLL_INTERVAL_MS: u64 = 50;          // Serial console input poll
+pub const CHARGING_ANIMATION_INTERVAL_MS: u64 = 500;   // Battery icon fill step while charging
+pub const FALL_ALERT_PULSE_INTERVAL_MS: u64 = 1000;    // Screen flash cadence
+pub const FALL_ALERT_SOS_EVERY_PULSES: u32 = 4;        // Replay SOS every N flashes …
+pub const FALL_ALERT_START_INTENSITY: u8 = 128;        // … starting at this strength …
+pub const FALL_ALERT_INTENSITY_STEP: u8 = 32;          // … and growing each replay
+#[cfg(feature = "capture")]
+pub const CAPTURE_REFRESH_INTERVAL_MS: u64 = 250;      // "REC" counter redraw
+pub const DEMO_STEP_INTERVAL_MS: u64 = 3000;           // Demo mode: time on each activity
+
+// ---------------------------------------------------------------------------
+// Clock
+// ---------------------------------------------------------------------------
+pub const CLOCK_USE_DS3231: bool = false;          // External RTC fitted (keeps time without power)
+pub const CLOCK_UTC_OFFSET_MIN: i32 = 0;           // Local time = UTC + this
 
 // ---------------------------------------------------------------------------
 // AI / Edge Impulse Model
 // ---------------------------------------------------------------------------
-pub const EI_RAW_SAMPLES_PER_FRAME: usize = 3;   // accX, accY, accZ
-pub const EI_RAW_SAMPLE_COUNT: usize = 125;       // 2-second window @ 62.5 Hz
-pub const EI_DSP_INPUT_FRAME_SIZE: usize = EI_RAW_SAMPLE_COUNT * EI_RAW_SAMPLES_PER_FRAME; // 375
+// 6-axis mode appends gyro X/Y/Z to every frame.  The deployed model must have
+// been trained on the same axes (Edge Impulse "accX + … + gyrZ" fusion).
+pub const EI_USE_GYRO: bool = false;
+pub const EI_RAW_SAMPLES_PER_FRAME: usize = if EI_USE_GYRO { 6 } else { 3 }; // acc[, gyr]
+pub const EI_RAW_SAMPLE_COUNT: usize = 125;       // Model's window: 2 s @ 62.5 Hz
+pub const EI_DSP_INPUT_FRAME_SIZE: usize = EI_RAW_SAMPLE_COUNT * EI_RAW_SAMPLES_PER_FRAME; // 375 / 750
+const _: () = assert!(EI_DSP_INPUT_FRAME_SIZE == if EI_USE_GYRO { 750 } else { 375 });
+// Window the AI task buffers and classifies.  The Edge Impulse back-end only
+// accepts the model's own length; other values (62 ≈ 1 s, 187 ≈ 3 s) are for
+// experimenting with the stub or a model retrained to match.
+pub const EI_WINDOW_SAMPLES: usize = EI_RAW_SAMPLE_COUNT;
+const _: () = assert!(EI_WINDOW_SAMPLES >= 2);
+const _: () = assert!(!cfg!(feature = "edge-impulse") || EI_WINDOW_SAMPLES == EI_RAW_SAMPLE_COUNT);
+pub const EI_WINDOW_HOP_SAMPLES: usize = 31;      // ~500 ms between overlapping windows
+const _: () = assert!(EI_WINDOW_HOP_SAMPLES > 0 && EI_WINDOW_HOP_SAMPLES <= EI_WINDOW_SAMPLES);
+pub const EI_WINDOW_SPAN_TOLERANCE_MS: u32 = 250; // Discard windows stretched / squeezed beyond this
+// Per-axis standardisation ((x − mean) / std over the window) before inference.
+// Enable only if the impulse was trained on standardised data; the stub
+// heuristic expects raw g values.
+pub const EI_NORMALIZE_FEATURES: bool = false;
+pub const EI_NORMALIZE_MIN_STD: f32 = 0.01;      // Clamp for a perfectly still window
+// Subtract a running per-axis mean (≈ gravity) from the accelerometer features
+// so they describe motion rather than wrist angle.  Captures stay raw: a model
+// for this setting must be trained on data put through the same subtraction
+// (same time constant), and a model trained with gravity must run without it.
+pub const EI_REMOVE_GRAVITY: bool = false;
+pub const EI_GRAVITY_TAU_MS: u32 = 1000;         // Running-mean time constant
 pub const EI_LABEL_COUNT: usize = 4;
 pub const EI_CONFIDENCE_THRESHOLD: f32 = 0.7;
+// Per-class thresholds in label order [idle, snake, updown, wave].  E.g.
+// [0.7, 0.5, 0.7, 0.85] never misses a fall and needs more certainty for "wave".
+pub const EI_CONFIDENCE_THRESHOLDS: [f32; EI_LABEL_COUNT] = [EI_CONFIDENCE_THRESHOLD; EI_LABEL_COUNT];
+pub const EI_HAS_ANOMALY: bool = false;           // Impulse includes an anomaly-detection block
+pub const EI_ANOMALY_THRESHOLD: f32 = 0.3;        // Above this the window is "unknown activity"
+pub const EI_SMOOTHING_WINDOW: usize = 3;         // K most recent windows considered
+pub const EI_VOTING_POLICY: VotingPolicy = VotingPolicy::Majority;
+pub const EI_FALL_BYPASS_SMOOTHING: bool = true;  // Report "snake" (fall) immediately
+pub const EI_UNKNOWN_AFTER_WINDOWS: u32 = 4;      // Windows with no class over threshold → "unknown"
+const _: () = assert!(EI_UNKNOWN_AFTER_WINDOWS as usize >= EI_SMOOTHING_WINDOW);
+pub const EI_SWITCH_AFTER_WINDOWS: u32 = 2;       // Windows a new activity must hold to be reported
+const _: () = assert!(EI_SWITCH_AFTER_WINDOWS > 0);
+pub const EI_IDLE_WINDOWS_BEFORE_SLOW: u32 = 20;  // ~10 s of "idle" → drop the sample rate
+pub const EI_WAKE_MOTION_G: f32 = 0.1;            // | |a| − 1 g | that restores the full rate
+pub const EI_INFERENCE_WARN_PCT: u32 = 75;        // Warn when inference eats this much of a hop
+pub const EI_PROGRESS_EVERY_SAMPLES: usize = 8;   // WindowProgress event rate (~128 ms)
+const _: () = assert!(EI_PROGRESS_EVERY_SAMPLES > 0);
+pub const DWELL_RESET_AT_MIDNIGHT: bool = true;   // Daily activity tally (else since boot)
+// OLED label per activity in label order [idle, snake, updown, wave], then
+// "unknown" (`ActivityClass::index`).  Defaults to the `UI_LANGUAGE` names;
+// replace with a literal array for different activity semantics.
+pub const ACTIVITY_DISPLAY_NAMES: [&str; EI_LABEL_COUNT + 1] = UI_LANGUAGE.strings().activities;
+
+// ---------------------------------------------------------------------------
+// IMU Calibration
+// ---------------------------------------------------------------------------
+pub const CALIBRATION_SAMPLES: usize = 125;             // ~2 s of readings averaged
+pub const CALIBRATION_MAX_ACCEL_VARIANCE: f32 = 0.0004; // g² (σ ≈ 0.02 g) — else "device moved"
+pub const CALIBRATION_MAX_GYRO_VARIANCE: f32 = 1.0;     // (°/s)²
+// Zeroing screen (double-click on the diagnostics screen)
+pub const CALIBRATION_COUNTDOWN_S: u32 = 3;             // Lay-flat countdown (click buzz fades)
+pub const CALIBRATION_UI_TIMEOUT_MS: u64 = 10_000;      // No result by then → shown as failed
+pub const CALIBRATION_RESULT_SHOW_MS: u64 = 2500;       // Outcome stays up this long
+const _: () = assert!(
+    CALIBRATION_UI_TIMEOUT_MS > 2 * CALIBRATION_SAMPLES as u64 * SENSOR_SAMPLE_INTERVAL_MS
+);
+
+// ---------------------------------------------------------------------------
+// Motion Wake (MPU6050 INT → PIN_IMU_INT)
+// ---------------------------------------------------------------------------
+pub const MOTION_WAKE_ENABLED: bool = true;       // Picking the watch up wakes it from deep sleep
+pub const MOTION_WAKE_THRESHOLD_MG: u32 = 80;     // High-passed accel change that counts (2 mg steps)
+pub const MOTION_WAKE_DURATION_MS: u8 = 40;       // …sustained this long (filters table knocks)
+pub const SLEEP_TIMER_WAKE_MS: Option<u64> = None; // E.g. Some(3_600_000): also wake hourly
+
+// ---------------------------------------------------------------------------
+// Wrist Orientation (raise to view)
+// ---------------------------------------------------------------------------
+pub const ORIENTATION_SMOOTHING_ALPHA: f32 = 0.1;  // Gravity low-pass (per sample @ 62.5 Hz)
+pub const ORIENTATION_MAX_PITCH_DEG: f32 = 75.0;   // Ignore roll beyond this (near vertical)
+pub const WRIST_RAISE_ROLL_DEG: f32 = 35.0;        // Roll that counts as "raised"
+pub const WRIST_RAISE_HYSTERESIS_DEG: f32 = 15.0;  // Must drop this far below to re-arm
+pub const WRIST_RAISE_MAX_DURATION_MS: u32 = 1000; // Low → raised roll must be this quick
+pub const WRIST_RAISE_SETTLE_MS: u32 = 250;        // …then held steady this long (not an arm swing)
+pub const WRIST_RAISE_STILL_G: f32 = 0.2;          // "Steady": | |a| − 1 g | below this
+pub const AUTO_ROTATE_ENABLED: bool = true;        // Flip the screen 180° when worn the other way
+pub const ORIENTATION_UP_SIGN: f32 = 1.0;          // +1: IMU +X points at the screen's top edge
+pub const ORIENTATION_FLIP_G: f32 = 0.5;           // Gravity along X that picks an edge (~30° tilt)
+pub const ORIENTATION_DEBOUNCE_MS: u32 = 1500;     // New edge must hold this long to flip
+
+// ---------------------------------------------------------------------------
+// Pedometer
+// ---------------------------------------------------------------------------
+pub const PEDOMETER_SMOOTHING_ALPHA: f32 = 0.3;   // |a| low-pass (per sample @ 62.5 Hz)
+pub const STEP_THRESHOLD_HIGH_G: f32 = 1.15;      // Peak above this counts as a step
+pub const STEP_THRESHOLD_LOW_G: f32 = 0.95;       // Must dip below this to re-arm
+pub const STEP_REFRACTORY_MS: u32 = 250;          // Min time between steps (≤ 4 steps/s)
+
+// ---------------------------------------------------------------------------
+// Activity History (flash log)
+// ---------------------------------------------------------------------------
+pub const HISTORY_LEN: usize = 128;                     // Entries kept (10 bytes each in NVS)
+const _: () = assert!(HISTORY_LEN <= u8::MAX as usize);
+pub const HISTORY_FLUSH_INTERVAL_MS: u64 = 15 * 60_000; // Flash writes at most every 15 min
 
 // ---------------------------------------------------------------------------
-// MPU6050 Sensor Scale Factors
+// Wear Detection
 // ---------------------------------------------------------------------------
-pub const ACCEL_SCALE_8G: f32 = 4096.0;   // LSB/g  at ±8 g
-pub const GYRO_SCALE_500: f32 = 65.5;     // LSB/°/s at ±500 °/s
+pub const WEAR_DETECTION_ENABLED: bool = true;      // Worn-but-still keeps the watch awake
+pub const WEAR_WINDOW_SAMPLES: usize = 125;         // 2 s @ 62.5 Hz per decision
+pub const WEAR_MIN_ACCEL_VARIANCE: f32 = 0.00004;   // g² (σ ≈ 6 mg); MPU6050 noise alone ≈ 3 mg
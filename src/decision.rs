@@ -0,0 +1,97 @@
+// PlastiWatch V2 — Classifier Decision Logic
+//
+// Turning per-class scores into an activity, kept apart from where the scores
+// come from (stub or Edge Impulse back-end) and from the firmware's runtime
+// settings: thresholds are passed in rather than read from `ei`.  Nothing
+// here logs, allocates or touches ESP-IDF — only `core` and the constants in
+// config.rs — so the file can be compiled into a host test crate as is.
+//
+//   decide     one window: the anomaly gate, then the highest score among
+//              the classes that clear their own threshold
+//   majority   smoothing: the class that won more than half of the windows
+//   weighted   smoothing: recency-weighted average confidence per class,
+//              reported if the best one clears its threshold
+//
+// Classes are indices in `ei::LABELS` order.  A NaN score ranks below every
+// number, so a degenerate model output never wins; on an exact tie the
+// earlier class wins.
+
+use core::cmp::Ordering;
+
+use crate::config::EI_LABEL_COUNT;
+
+/// One score (or threshold) per class.
+pub type Scores = [f32; EI_LABEL_COUNT];
+
+/// The class a window was classified as, and its score.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Decision {
+    pub index: usize,
+    pub confidence: f32,
+}
+
+/// Order two scores, treating NaN as the lowest possible score.
+pub fn compare_scores(a: f32, b: f32) -> Ordering {
+    let key = |s: f32| if s.is_nan() { f32::NEG_INFINITY } else { s };
+    key(a).total_cmp(&key(b))
+}
+
+/// Decide one window: nothing if `anomaly` is above `anomaly_threshold`,
+/// otherwise the highest-scoring class among those at or above their own
+/// threshold, or nothing if none is.
+pub fn decide(
+    scores: &Scores,
+    thresholds: &Scores,
+    anomaly: Option<f32>,
+    anomaly_threshold: f32,
+) -> Option<Decision> {
+    if anomaly.is_some_and(|a| a > anomaly_threshold) {
+        return None;
+    }
+    (0..EI_LABEL_COUNT)
+        .filter(|&i| scores[i] >= thresholds[i])
+        .fold(None, |best: Option<Decision>, i| match best {
+            Some(b) if compare_scores(scores[i], b.confidence) != Ordering::Greater => Some(b),
+            _ => Some(Decision {
+                index: i,
+                confidence: scores[i],
+            }),
+        })
+}
+
+/// The class that won more than half of `history` (`None` entries are
+/// windows with no winner, and still count toward the total).
+pub fn majority(history: &[Option<usize>]) -> Option<usize> {
+    let mut votes = [0usize; EI_LABEL_COUNT];
+    for &index in history.iter().flatten() {
+        votes[index] += 1;
+    }
+    let (best, &count) = votes.iter().enumerate().max_by_key(|&(_, &v)| v)?;
+    (count * 2 > history.len()).then_some(best)
+}
+
+/// Average each class's confidence over `history` (oldest first), the
+/// oldest window weighing 1 and the newest `history.len()`; a window with no
+/// winner adds weight but no score.  Returns the best class if its average
+/// clears its threshold.
+pub fn weighted(history: &[Option<(usize, f32)>], thresholds: &Scores) -> Option<usize> {
+    let mut totals = [0.0f32; EI_LABEL_COUNT];
+    let mut total_weight = 0.0f32;
+    for (age, window) in history.iter().enumerate() {
+        let weight = (age + 1) as f32;
+        total_weight += weight;
+        if let Some((index, confidence)) = *window {
+            totals[index] += weight * confidence;
+        }
+    }
+
+    let averages = totals.map(|t| t / total_weight);
+    let best = (0..EI_LABEL_COUNT).reduce(|best, i| {
+        if compare_scores(averages[i], averages[best]) == Ordering::Greater {
+            i
+        } else {
+            best
+        }
+    })?;
+    (averages[best] >= thresholds[best]).then_some(best)
+}
@@ -0,0 +1,114 @@
+// PlastiWatch V2 — Shared I2C Bus Setup & Recovery
+//
+// `open` creates the I2C0 driver on `PIN_I2C_SDA` / `PIN_I2C_SCL` at
+// `I2C_BAUD_HZ`; `main` leaks it into the `'static` mutex every driver
+// shares (`SharedBus`).
+//
+// A slave reset or brown-out in the middle of a read can leave it holding
+// SDA low, waiting for clocks that never come; every transaction then times
+// out and both the OLED and the IMU go dead.  `recover` fixes that in place:
+// with the bus mutex held it deletes the driver, clocks SCL by hand (up to
+// nine pulses, until the slave lets go of SDA), issues a STOP, and installs
+// a fresh driver in the same slot.  Other tasks are blocked on the mutex
+// meanwhile and simply carry on with the new driver afterwards — the
+// `SharedBus` reference they hold never changes.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use esp_idf_hal::gpio::AnyIOPin;
+use esp_idf_hal::i2c::{I2C0, I2cConfig, I2cDriver};
+use esp_idf_hal::prelude::*;
+
+use crate::config::*;
+use crate::drivers::imu::SharedBus;
+
+/// Half an SCL period while bit-banging (~100 kHz).
+const BITBANG_HALF_PERIOD_US: u32 = 5;
+
+/// Bus recoveries performed since boot.
+static RECOVERIES: AtomicU32 = AtomicU32::new(0);
+
+pub fn recoveries() -> u32 {
+    RECOVERIES.load(Ordering::Relaxed)
+}
+
+/// Create the I2C driver on the configured pins and speed.
+pub fn open(i2c: I2C0) -> anyhow::Result<I2cDriver<'static>> {
+    // SAFETY: nothing else drives `PIN_I2C_SDA` / `PIN_I2C_SCL`; the matching
+    // `peripherals.pins` fields are never used.
+    let (sda, scl) = unsafe { (AnyIOPin::new(PIN_I2C_SDA), AnyIOPin::new(PIN_I2C_SCL)) };
+    let config = I2cConfig::new().baudrate(I2C_BAUD_HZ.Hz());
+    Ok(I2cDriver::new(i2c, sda, scl, &config)?)
+}
+
+/// Unstick the bus and reinstall its driver.  Holds the bus lock throughout.
+///
+/// If no new driver can be created the chip restarts: the slot would
+/// otherwise hold a deleted driver.
+pub fn recover(bus: SharedBus) {
+    let mut driver = bus.lock().unwrap();
+    let count = RECOVERIES.fetch_add(1, Ordering::Relaxed) + 1;
+    log::warn!("I2C bus recovery #{}", count);
+
+    // SAFETY: the old driver is dropped exactly once here and the slot is
+    // written again below before the guard is released (or we restart).
+    unsafe { core::ptr::drop_in_place(&mut *driver) };
+
+    let released = unsafe { clock_out_stuck_slave() };
+    if !released {
+        log::error!("I2C: SDA still held low after 9 clocks");
+    }
+
+    for attempt in 1..=I2C_MAX_RETRIES {
+        // SAFETY: the previous I2C0 driver was deleted above.
+        match open(unsafe { I2C0::new() }) {
+            Ok(fresh) => {
+                unsafe { core::ptr::write(&mut *driver, fresh) };
+                log::info!("I2C bus recovered");
+                return;
+            }
+            Err(e) => log::error!("I2C driver re-init failed ({}/{}): {}", attempt, I2C_MAX_RETRIES, e),
+        }
+    }
+
+    log::error!("I2C bus unrecoverable — restarting");
+    unsafe { esp_idf_sys::esp_restart() }
+}
+
+/// Toggle SCL until the slave releases SDA (at most nine clocks — enough to
+/// finish any byte plus its ACK), then generate a STOP.  Returns whether SDA
+/// ended up high.
+unsafe fn clock_out_stuck_slave() -> bool {
+    use esp_idf_sys::*;
+
+    let delay = || esp_rom_delay_us(BITBANG_HALF_PERIOD_US);
+    for pin in [PIN_I2C_SDA, PIN_I2C_SCL] {
+        gpio_reset_pin(pin);
+        gpio_set_direction(pin, gpio_mode_t_GPIO_MODE_INPUT_OUTPUT_OD);
+        gpio_set_pull_mode(pin, gpio_pull_mode_t_GPIO_PULLUP_ONLY);
+        gpio_set_level(pin, 1);
+    }
+    delay();
+
+    for _ in 0..9 {
+        if gpio_get_level(PIN_I2C_SDA) == 1 {
+            break;
+        }
+        gpio_set_level(PIN_I2C_SCL, 0);
+        delay();
+        gpio_set_level(PIN_I2C_SCL, 1);
+        delay();
+    }
+
+    // STOP: SDA low → high while SCL is high.
+    gpio_set_level(PIN_I2C_SCL, 0);
+    delay();
+    gpio_set_level(PIN_I2C_SDA, 0);
+    delay();
+    gpio_set_level(PIN_I2C_SCL, 1);
+    delay();
+    gpio_set_level(PIN_I2C_SDA, 1);
+    delay();
+
+    gpio_get_level(PIN_I2C_SDA) == 1
+}
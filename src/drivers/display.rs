@@ -1,7 +1,31 @@
 // PlastiWatch V2 — SSD1306 OLED Display Driver (128×64, I2C)
 //
 // Custom register-level driver that implements `embedded_graphics::DrawTarget`.
-// Manages a 1024-byte frame buffer flushed to the display via I2C.
+// All drawing goes into a 1024-byte in-RAM back buffer; `present()` pushes the
+// finished frame to the panel under one bus lock so a half-drawn frame is
+// never visible.
+//
+// Every byte write that actually changes the buffer marks its page and column
+// dirty, and `present()` only sends the dirty column span of each dirty page
+// (via the SSD1306 column/page address window).  Pages a screen never
+// touches (blank bands, regions outside the text) cost no bus time, leaving
+// more of the bus to the IMU.
+//
+// A copy of what the panel currently shows is kept as well, and each dirty
+// span is trimmed to the bytes that really differ from it before sending.
+// Screens are redrawn from scratch (clear, then draw) on every UI event, so
+// an identical redraw dirties plenty but flushes nothing.  When the panel RAM
+// can't be trusted (fresh driver, after power-up) `force_flush()` sends the
+// full frame.
+//
+// A caller that redraws on every event can cap the frame rate with
+// `set_frame_interval`: a `present()` that comes too soon after the last frame
+// leaves its changes pending, and a later `present()` sends them (the UI task
+// calls it every loop).  Without a cap, `present()` always sends.
+//
+// Text too wide for the panel can be shown as a marquee with
+// `show_scrolling_text`: each call draws the next frame, one column further
+// along, so the caller's refresh tick sets the scroll speed.
 
 use std::sync::Mutex;
 
@@ -9,19 +33,28 @@ use embedded_graphics::{
     draw_target::DrawTarget,
     geometry::{OriginDimensions, Point, Size},
     image::{Image, ImageRaw},
-    mono_font::{ascii::FONT_6X10, MonoTextStyle},
+    mono_font::{
+        iso_8859_1::{FONT_10X20, FONT_6X10},
+        MonoFont, MonoTextStyle,
+    },
     pixelcolor::BinaryColor,
     prelude::*,
-    primitives::{PrimitiveStyle, Rectangle},
-    text::{Alignment, Text},
+    primitives::{Arc, PrimitiveStyle, Rectangle},
+    text::{Alignment, Baseline, Text},
 };
 use esp_idf_hal::i2c::I2cDriver;
 
+use crate::clock::TimeOfDay;
 use crate::config::*;
+use crate::dwell;
 use crate::events::ActivityClass;
+use crate::i18n;
+use crate::state::SystemState;
+use crate::ticks;
 
 // ---------------------------------------------------------------------------
-// PlastiBytes logo bitmap — 128×64 monochrome, SSD1306 page format
+// PlastiBytes logo bitmap — 128×64 monochrome, row-major, MSB first
+// (converted to the SSD1306 page format by `draw_bitmap`)
 // Extracted from the original C++ DisplayDriver.
 // ---------------------------------------------------------------------------
 #[rustfmt::skip]
@@ -107,28 +140,106 @@ const CMD_SET_MULTIPLEX: u8 = 0xA8;
 const CMD_SET_DISPLAY_OFFSET: u8 = 0xD3;
 const CMD_SET_START_LINE: u8 = 0x40;
 const CMD_CHARGE_PUMP: u8 = 0x8D;
+const CHARGE_PUMP_ON: u8 = 0x14;
+const CHARGE_PUMP_OFF: u8 = 0x10;
 const CMD_MEMORY_MODE: u8 = 0x20;
 const CMD_SEG_REMAP: u8 = 0xA1;
+const CMD_SEG_NORMAL: u8 = 0xA0;
 const CMD_COM_SCAN_DEC: u8 = 0xC8;
+const CMD_COM_SCAN_INC: u8 = 0xC0;
 const CMD_SET_COM_PINS: u8 = 0xDA;
 const CMD_SET_CONTRAST: u8 = 0x81;
 const CMD_SET_PRECHARGE: u8 = 0xD9;
 const CMD_SET_VCOMH: u8 = 0xDB;
 const CMD_DISPLAY_ALL_ON_RESUME: u8 = 0xA4;
 const CMD_NORMAL_DISPLAY: u8 = 0xA6;
+const CMD_INVERT_DISPLAY: u8 = 0xA7;
 const CMD_SET_COLUMN_ADDR: u8 = 0x21;
 const CMD_SET_PAGE_ADDR: u8 = 0x22;
 
+const PAGES: usize = SCREEN_HEIGHT as usize / 8;
+
 // I2C control bytes
 const CTRL_CMD: u8 = 0x00;  // Co=0, D/C#=0 → command
 const CTRL_DATA: u8 = 0x40; // Co=0, D/C#=1 → data
 
+// ---------------------------------------------------------------------------
+// Fonts
+// ---------------------------------------------------------------------------
+
+/// Text size for [`OledDisplay::draw_text`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FontSize {
+    /// 6×10 — labels and status lines (21 characters per row).
+    Small,
+    /// 10×20 — time of day and counts, readable at a glance (12 per row).
+    Large,
+}
+
+impl FontSize {
+    fn font(self) -> &'static MonoFont<'static> {
+        match self {
+            FontSize::Small => &FONT_6X10,
+            FontSize::Large => &FONT_10X20,
+        }
+    }
+
+    /// Width and height of one character cell in pixels.
+    pub fn glyph_size(self) -> Size {
+        self.font().character_size
+    }
+
+    /// Left edge that centres `text` horizontally on the screen.  Negative
+    /// if the text is wider than the screen (it is then clipped both sides).
+    fn centered_x(self, text: &str) -> i32 {
+        let width = (text.chars().count() as u32 * self.glyph_size().width) as i32;
+        (SCREEN_WIDTH as i32 - width) / 2
+    }
+}
+
+/// Stage of the IMU zeroing screen, for [`OledDisplay::show_calibration`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalibrationView {
+    /// Seconds left before the measurement starts.
+    Countdown(u32),
+    Measuring,
+    /// Outcome: `true` = the new offsets are in use.
+    Done(bool),
+}
+
 // ---------------------------------------------------------------------------
 // OledDisplay — frame-buffered SSD1306 driver
 // ---------------------------------------------------------------------------
 pub struct OledDisplay {
     bus: SharedBus,
     buffer: [u8; DISPLAY_BUFFER_SIZE],
+    /// Changed column span per page since the last `present`; `None` = clean.
+    dirty: [Option<(u8, u8)>; PAGES],
+    /// What the panel RAM holds (the last frame sent), unless `stale`.
+    shown: [u8; DISPLAY_BUFFER_SIZE],
+    stale: bool,
+    /// Charging animation frame for the battery icon; `None` = not charging.
+    charge_frame: Option<u8>,
+    /// Show the "no IMU" marker next to the battery icon.
+    sensor_lost: bool,
+    /// How full the AI feature window is (0.0–1.0); 0.0 hides the dot.
+    window_progress: f32,
+    /// User contrast setting, and whether it is currently overridden by the
+    /// dim level.
+    brightness: u8,
+    dimmed: bool,
+    /// User invert setting, and a temporary flash (fall alert) that flips
+    /// it; the panel shows `inverted ^ flash`.
+    inverted: bool,
+    flash: bool,
+    /// Rendered upside down (panel mirrored on both axes).
+    rotated: bool,
+    /// Minimum time between frames (0 = none) and `now_ms()` of the last.
+    frame_interval_ms: u64,
+    last_frame_ms: u32,
+    /// Marquee state: the text being scrolled and how far it has moved.
+    scroll_text: String,
+    scroll_offset: u32,
 }
 
 impl OledDisplay {
@@ -136,6 +247,23 @@ impl OledDisplay {
         Self {
             bus,
             buffer: [0u8; DISPLAY_BUFFER_SIZE],
+            // Another driver instance (or nothing) drew the current contents:
+            // the first `present` sends the full frame.
+            dirty: [Some((0, SCREEN_WIDTH as u8 - 1)); PAGES],
+            shown: [0u8; DISPLAY_BUFFER_SIZE],
+            stale: true,
+            charge_frame: None,
+            sensor_lost: false,
+            window_progress: 0.0,
+            brightness: OLED_DEFAULT_BRIGHTNESS,
+            dimmed: false,
+            inverted: false,
+            flash: false,
+            rotated: false,
+            frame_interval_ms: 0,
+            last_frame_ms: 0,
+            scroll_text: String::new(),
+            scroll_offset: 0,
         }
     }
 
@@ -143,14 +271,14 @@ impl OledDisplay {
 
     fn send_command(&self, cmd: u8) -> anyhow::Result<()> {
         let mut bus = self.bus.lock().unwrap();
-        bus.write(I2C_ADDR_OLED, &[CTRL_CMD, cmd], I2C_TIMEOUT_TICKS)?;
+        bus.write(I2C_ADDR_OLED, &[CTRL_CMD, cmd], I2C_TIMEOUT_TICKS_OLED)?;
         Ok(())
     }
 
     fn send_commands(&self, cmds: &[u8]) -> anyhow::Result<()> {
         let mut bus = self.bus.lock().unwrap();
         for &cmd in cmds {
-            bus.write(I2C_ADDR_OLED, &[CTRL_CMD, cmd], I2C_TIMEOUT_TICKS)?;
+            bus.write(I2C_ADDR_OLED, &[CTRL_CMD, cmd], I2C_TIMEOUT_TICKS_OLED)?;
         }
         Ok(())
     }
@@ -161,7 +289,7 @@ impl OledDisplay {
     pub fn is_connected(&self) -> bool {
         let mut bus = self.bus.lock().unwrap();
         // Send a NOP-like command; success means ACK received.
-        bus.write(I2C_ADDR_OLED, &[CTRL_CMD, CMD_DISPLAY_ALL_ON_RESUME], I2C_TIMEOUT_TICKS)
+        bus.write(I2C_ADDR_OLED, &[CTRL_CMD, CMD_DISPLAY_ALL_ON_RESUME], I2C_TIMEOUT_TICKS_OLED)
             .is_ok()
     }
 
@@ -173,110 +301,362 @@ impl OledDisplay {
             CMD_SET_MULTIPLEX, 0x3F,          // 64 lines
             CMD_SET_DISPLAY_OFFSET, 0x00,
             CMD_SET_START_LINE,               // line 0
-            CMD_CHARGE_PUMP, 0x14,            // enable charge pump
-            CMD_MEMORY_MODE, 0x00,            // horizontal addressing
-            CMD_SEG_REMAP,                    // column 127 = SEG0
-            CMD_COM_SCAN_DEC,                 // scan from COM63 to COM0
+            CMD_CHARGE_PUMP, CHARGE_PUMP_ON,
             CMD_SET_COM_PINS, 0x12,           // alt COM pin config
-            CMD_SET_CONTRAST, 0xCF,
             CMD_SET_PRECHARGE, 0xF1,
             CMD_SET_VCOMH, 0x40,
-            CMD_DISPLAY_ALL_ON_RESUME,
-            CMD_NORMAL_DISPLAY,
-            CMD_DISPLAY_ON,
         ])?;
+        self.send_commands(&self.mode_commands())?;
+        self.send_command(CMD_DISPLAY_ON)?;
 
+        // Panel RAM is undefined after power-up: send the whole frame.
         self.clear_buffer();
-        self.flush()?;
+        self.force_flush()?;
         log::info!("SSD1306 OLED initialised (128x64)");
         Ok(())
     }
 
-    /// Zero the frame buffer (does NOT flush to hardware).
+    /// Zero the frame buffer (does NOT touch the hardware).
     pub fn clear_buffer(&mut self) {
-        self.buffer.fill(0);
+        for idx in 0..DISPLAY_BUFFER_SIZE {
+            self.set_byte(idx, 0);
+        }
     }
 
-    /// Push the entire frame buffer to the display over I2C.
-    pub fn flush(&self) -> anyhow::Result<()> {
-        // Set addressing window to full screen
-        self.send_commands(&[CMD_SET_COLUMN_ADDR, 0, 127])?;
-        self.send_commands(&[CMD_SET_PAGE_ADDR, 0, 7])?;
+    /// Push the changed parts of the back buffer to the display as one frame.
+    /// A no-op when the frame matches what the panel already shows, or — under
+    /// a `set_frame_interval` cap — when the last frame was sent too recently;
+    /// the changes then stay pending for the next call.
+    pub fn present(&mut self) -> anyhow::Result<()> {
+        if self.dirty.iter().all(Option::is_none) {
+            return Ok(());
+        }
+        let now = crate::now_ms();
+        if (ticks::elapsed_ms(now, self.last_frame_ms) as u64) < self.frame_interval_ms {
+            return Ok(());
+        }
+        self.last_frame_ms = now;
+        self.send_frame()
+    }
+
+    /// Send frames at most every `ms` (0 = as often as `present` is called).
+    pub fn set_frame_interval(&mut self, ms: u64) {
+        self.frame_interval_ms = ms;
+    }
 
-        // Send buffer page-by-page (128 data bytes + 1 control byte per page)
+    /// Send the dirty spans now.  The bus lock is held across every page's
+    /// addressing window and data transfer, so the IMU can't interleave with
+    /// a half-sent frame.  A page that fails to send stays dirty and is
+    /// retried by the next call.
+    fn send_frame(&mut self) -> anyhow::Result<()> {
         let mut bus = self.bus.lock().unwrap();
-        let mut page_buf = [0u8; 129];
-        page_buf[0] = CTRL_DATA;
+        let width = SCREEN_WIDTH as usize;
+
+        for page in 0..PAGES {
+            let Some((first, last)) = self.dirty[page] else {
+                continue;
+            };
+            let row = page * width;
+
+            // Trim the span to the bytes that differ from the panel.
+            let (first, last) = if self.stale {
+                (first, last)
+            } else {
+                let differs = |&col: &u8| {
+                    let idx = row + col as usize;
+                    self.buffer[idx] != self.shown[idx]
+                };
+                match (first..=last).find(differs) {
+                    Some(f) => (f, (first..=last).rev().find(differs).unwrap_or(f)),
+                    None => {
+                        self.dirty[page] = None;
+                        continue;
+                    }
+                }
+            };
 
-        for page in 0..8 {
-            let start = page * 128;
-            page_buf[1..].copy_from_slice(&self.buffer[start..start + 128]);
-            bus.write(I2C_ADDR_OLED, &page_buf, I2C_TIMEOUT_TICKS)?;
+            // Addressing window: dirty columns of this page only (Co=0: all
+            // following bytes are commands)
+            let page_addr = page as u8;
+            bus.write(
+                I2C_ADDR_OLED,
+                &[CTRL_CMD, CMD_SET_COLUMN_ADDR, first, last, CMD_SET_PAGE_ADDR, page_addr, page_addr],
+                I2C_TIMEOUT_TICKS_OLED,
+            )?;
+
+            // 1 control byte + the dirty span in a single data transfer
+            let span = row + first as usize..=row + last as usize;
+            let len = span.clone().count();
+            let mut frame = [0u8; SCREEN_WIDTH as usize + 1];
+            frame[0] = CTRL_DATA;
+            frame[1..=len].copy_from_slice(&self.buffer[span.clone()]);
+            bus.write(I2C_ADDR_OLED, &frame[..=len], I2C_TIMEOUT_TICKS_OLED)?;
+
+            self.shown[span.clone()].copy_from_slice(&self.buffer[span]);
+            self.dirty[page] = None;
+        }
+
+        if self.dirty.iter().all(Option::is_none) {
+            self.stale = false;
         }
         Ok(())
     }
 
-    /// Power off the display panel (OLED segments off, low power).
+    /// Send the entire back buffer regardless of what the panel is believed
+    /// to show — e.g. after waking the panel, when its RAM is unknown.
+    pub fn force_flush(&mut self) -> anyhow::Result<()> {
+        self.stale = true;
+        self.mark_all_dirty();
+        self.last_frame_ms = crate::now_ms();
+        self.send_frame()
+    }
+
+    /// Write one buffer byte, marking its page/column dirty if it changed.
+    fn set_byte(&mut self, idx: usize, value: u8) {
+        if self.buffer[idx] == value {
+            return;
+        }
+        self.buffer[idx] = value;
+
+        let width = SCREEN_WIDTH as usize;
+        let (page, col) = (idx / width, (idx % width) as u8);
+        self.dirty[page] = Some(match self.dirty[page] {
+            Some((first, last)) => (first.min(col), last.max(col)),
+            None => (col, col),
+        });
+    }
+
+    /// Treat the whole panel as stale (its RAM no longer matches the buffer).
+    fn mark_all_dirty(&mut self) {
+        self.dirty = [Some((0, SCREEN_WIDTH as u8 - 1)); PAGES];
+    }
+
+    /// Put the panel into its sleep state: display off, then charge pump
+    /// off (the datasheet order), so it draws only a few µA.  Panel RAM is
+    /// kept.
     pub fn turn_off(&self) -> anyhow::Result<()> {
-        self.send_command(CMD_DISPLAY_OFF)
+        self.send_commands(&[CMD_DISPLAY_OFF, CMD_CHARGE_PUMP, CHARGE_PUMP_OFF])
     }
 
-    /// Power on the display panel.
+    /// Wake the panel from `turn_off`: charge pump on, the same addressing,
+    /// orientation, contrast and invert settings `init` uses, then display
+    /// on.
     pub fn turn_on(&self) -> anyhow::Result<()> {
+        self.send_commands(&[CMD_CHARGE_PUMP, CHARGE_PUMP_ON])?;
+        self.send_commands(&self.mode_commands())?;
         self.send_command(CMD_DISPLAY_ON)
     }
 
+    /// The settings `init` establishes and `turn_on` re-asserts, kept in one
+    /// list so the two can't drift apart.
+    #[rustfmt::skip]
+    fn mode_commands(&self) -> [u8; 8] {
+        [
+            CMD_MEMORY_MODE, 0x00,            // horizontal addressing
+            // Column 127 = SEG0, scan COM63 → COM0 (both flipped when rotated)
+            if self.rotated { CMD_SEG_NORMAL } else { CMD_SEG_REMAP },
+            if self.rotated { CMD_COM_SCAN_INC } else { CMD_COM_SCAN_DEC },
+            CMD_SET_CONTRAST, self.contrast_level(),
+            CMD_DISPLAY_ALL_ON_RESUME,
+            if self.inverted ^ self.flash { CMD_INVERT_DISPLAY } else { CMD_NORMAL_DISPLAY },
+        ]
+    }
+
+    /// Set the user brightness (SSD1306 contrast, 0–255).  While dimmed it
+    /// is stored and applied on un-dimming.
+    pub fn set_brightness(&mut self, level: u8) -> anyhow::Result<()> {
+        self.brightness = level;
+        self.apply_contrast()
+    }
+
+    /// Drop to `OLED_DIM_BRIGHTNESS` (or back to the user brightness).
+    pub fn set_dimmed(&mut self, dimmed: bool) -> anyhow::Result<()> {
+        self.dimmed = dimmed;
+        self.apply_contrast()
+    }
+
+    fn apply_contrast(&self) -> anyhow::Result<()> {
+        self.send_commands(&[CMD_SET_CONTRAST, self.contrast_level()])
+    }
+
+    fn contrast_level(&self) -> u8 {
+        if self.dimmed {
+            OLED_DIM_BRIGHTNESS.min(self.brightness)
+        } else {
+            self.brightness
+        }
+    }
+
+    /// User invert setting: dark-on-light rendering by inverting every pixel
+    /// in hardware (the back buffer is untouched), so it holds on every screen.
+    pub fn set_invert(&mut self, inverted: bool) -> anyhow::Result<()> {
+        self.inverted = inverted;
+        self.apply_invert()
+    }
+
+    pub fn inverted(&self) -> bool {
+        self.inverted
+    }
+
+    /// Temporarily flip the current invert state (alert flashing).
+    pub fn set_flash(&mut self, flash: bool) -> anyhow::Result<()> {
+        self.flash = flash;
+        self.apply_invert()
+    }
+
+    fn apply_invert(&self) -> anyhow::Result<()> {
+        self.send_command(if self.inverted ^ self.flash {
+            CMD_INVERT_DISPLAY
+        } else {
+            CMD_NORMAL_DISPLAY
+        })
+    }
+
+    /// Render rotated 180° (or back).  Done in hardware by mirroring the
+    /// panel's column and row scan, so drawing code is unaffected.  The
+    /// column mirror only applies to data written after it, so the whole
+    /// frame is re-sent.
+    pub fn set_rotated(&mut self, rotated: bool) -> anyhow::Result<()> {
+        if rotated == self.rotated {
+            return Ok(());
+        }
+        self.rotated = rotated;
+        self.send_commands(&[
+            if rotated { CMD_SEG_NORMAL } else { CMD_SEG_REMAP },
+            if rotated { CMD_COM_SCAN_INC } else { CMD_COM_SCAN_DEC },
+        ])?;
+        self.force_flush()
+    }
+
     // -- high-level screens -------------------------------------------------
 
     /// Show the PlastiBytes logo bitmap full-screen.
     pub fn show_logo(&mut self) -> anyhow::Result<()> {
-        // The logo constant is in row-major bit format (MSB-first per byte, row by row).
-        // SSD1306 GDDRAM is in page format (each byte = 8 vertical pixels in a column).
-        // We must convert from row-major to page format.
+        self.show_bitmap(&LOGO_BITMAP, SCREEN_WIDTH, SCREEN_HEIGHT, 0, 0)
+    }
+
+    /// Show a 1-bpp bitmap on a blank screen; see [`Self::draw_bitmap`].
+    pub fn show_bitmap(
+        &mut self,
+        data: &[u8],
+        width: u32,
+        height: u32,
+        x: i32,
+        y: i32,
+    ) -> anyhow::Result<()> {
         self.clear_buffer();
-        for y in 0..64u32 {
-            for x in 0..128u32 {
-                let src_byte = (y * 16 + x / 8) as usize;
-                let src_bit = 7 - (x % 8);
-                if (LOGO_BITMAP[src_byte] >> src_bit) & 1 == 1 {
-                    let page = (y / 8) as usize;
-                    let bit = y % 8;
-                    self.buffer[page * 128 + x as usize] |= 1 << bit;
-                }
-            }
+        self.draw_bitmap(data, width, height, x, y)?;
+        self.present()
+    }
+
+    /// Blit a 1-bpp bitmap into the back buffer with its top-left corner at
+    /// (`x`, `y`).  `data` is row-major, MSB first, each row padded to a
+    /// whole byte — the layout of [`LOGO_BITMAP`] and of most image-to-C
+    /// converters.  Set bits light pixels; clear bits leave the buffer as it
+    /// was.  Whatever hangs off the screen edge is clipped.
+    ///
+    /// Errors (drawing nothing) if the bitmap is empty, larger than the
+    /// screen, or `data` is too short for `width` × `height`.
+    pub fn draw_bitmap(
+        &mut self,
+        data: &[u8],
+        width: u32,
+        height: u32,
+        x: i32,
+        y: i32,
+    ) -> anyhow::Result<()> {
+        if width == 0 || height == 0 || width > SCREEN_WIDTH || height > SCREEN_HEIGHT {
+            anyhow::bail!(
+                "bitmap {}x{} doesn't fit the {}x{} screen",
+                width,
+                height,
+                SCREEN_WIDTH,
+                SCREEN_HEIGHT
+            );
+        }
+        let stride = width.div_ceil(8) as usize;
+        let needed = stride * height as usize;
+        if data.len() < needed {
+            anyhow::bail!("bitmap {}x{} needs {} bytes, got {}", width, height, needed, data.len());
         }
-        self.flush()
+
+        let pixels = (0..height).flat_map(|row| (0..width).map(move |col| (row, col)));
+        let lit = pixels.filter_map(|(row, col)| {
+            let byte = data[row as usize * stride + col as usize / 8];
+            ((byte >> (7 - col % 8)) & 1 == 1)
+                .then(|| Pixel(Point::new(x + col as i32, y + row as i32), BinaryColor::On))
+        });
+        self.draw_iter(lit).unwrap(); // clips to the screen, never fails
+        Ok(())
     }
 
     /// Show centred text on a blank screen (used for boot splash).
     pub fn show_centered_text(&mut self, text: &str) -> anyhow::Result<()> {
         self.clear_buffer();
-        let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
-        let x = 64; // centre of 128-px wide screen
-        let y = 34; // roughly vertically centred
-        Text::with_alignment(text, Point::new(x, y), style, Alignment::Center)
-            .draw(self)
-            .unwrap(); // draw to buffer never fails
-        self.flush()
+        let size = FontSize::Small;
+        let y = (SCREEN_HEIGHT - size.glyph_size().height) as i32 / 2;
+        self.draw_text(size.centered_x(text), y, text, size);
+        self.present()
+    }
+
+    /// Large single line; text wider than the screen scrolls right-to-left,
+    /// one column per call (call it from the refresh tick).  Changing the
+    /// text restarts the scroll.
+    pub fn show_scrolling_text(&mut self, text: &str) -> anyhow::Result<()> {
+        self.clear_buffer();
+        let font = &FONT_10X20;
+        let style = MonoTextStyle::new(font, BinaryColor::On);
+        let y = 38;
+
+        let width = (text.chars().count() as u32 * font.character_size.width) as i32;
+        if width <= SCREEN_WIDTH as i32 {
+            Text::with_alignment(text, Point::new(64, y), style, Alignment::Center)
+                .draw(self)
+                .unwrap();
+            return self.present();
+        }
+
+        if self.scroll_text != text {
+            self.scroll_text = text.to_string();
+            self.scroll_offset = 0;
+        }
+
+        // Draw the text twice, a gap apart, so it wraps around seamlessly.
+        let period = width + SCROLL_GAP_PX;
+        let x = -(self.scroll_offset as i32);
+        for start in [x, x + period] {
+            Text::new(text, Point::new(start, y), style).draw(self).unwrap();
+        }
+        self.scroll_offset = (self.scroll_offset + 1) % period as u32;
+
+        self.present()
     }
 
     /// Default UI: logo + "PlastiBytes" label underneath.
     pub fn show_default_ui(&mut self) -> anyhow::Result<()> {
-        self.show_logo()?;
+        self.clear_buffer();
+        self.draw_bitmap(&LOGO_BITMAP, SCREEN_WIDTH, SCREEN_HEIGHT, 0, 0)?;
         // Overlay text at bottom (logo occupies upper ~45 rows)
         let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
         Text::with_alignment("PlastiBytes", Point::new(64, 60), style, Alignment::Center)
             .draw(self)
             .unwrap();
-        self.flush()
+        self.present()
     }
 
-    /// Activity display: activity name centred + battery indicator top-right.
-    pub fn show_activity(&mut self, activity: ActivityClass, battery_pct: f32) -> anyhow::Result<()> {
+    /// Activity display: activity name centred, battery indicator top-right,
+    /// step count along the bottom.
+    pub fn show_activity(
+        &mut self,
+        activity: ActivityClass,
+        confidence: f32,
+        battery_pct: f32,
+        steps: u32,
+    ) -> anyhow::Result<()> {
         self.clear_buffer();
 
         // Battery icon (top-right corner)
-        self.draw_battery(battery_pct);
+        self.draw_battery_indicator(battery_pct);
 
         // Activity label centred
         let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
@@ -289,7 +669,165 @@ impl OledDisplay {
         .draw(self)
         .unwrap();
 
-        self.flush()
+        // Classifier confidence bar under the label, with the "analysing"
+        // dot below it
+        self.draw_confidence_bar(confidence);
+        self.draw_window_progress();
+
+        // Step count along the bottom
+        let line = format!("{} {}", steps, i18n::strings().steps);
+        Text::with_alignment(&line, Point::new(64, 60), style, Alignment::Center)
+            .draw(self)
+            .unwrap();
+
+        self.present()
+    }
+
+    /// Large HH:MM with seconds below; "--:--" until the time is set.
+    pub fn show_clock(&mut self, time: Option<TimeOfDay>, battery_pct: f32) -> anyhow::Result<()> {
+        self.clear_buffer();
+        self.draw_battery_indicator(battery_pct);
+
+        let (hhmm, ss) = match time {
+            Some(t) => (format!("{:02}:{:02}", t.hour, t.minute), format!("{:02}", t.second)),
+            None => ("--:--".into(), i18n::strings().time_not_set.into()),
+        };
+        self.draw_text(FontSize::Large.centered_x(&hhmm), 23, &hhmm, FontSize::Large);
+        self.draw_text(FontSize::Small.centered_x(&ss), 49, &ss, FontSize::Small);
+
+        self.present()
+    }
+
+    /// Time per activity, longest first, e.g. "walking  42m".  Activities
+    /// with no time yet are left out.
+    pub fn show_summary(&mut self, totals_ms: &[u32; EI_LABEL_COUNT + 1]) -> anyhow::Result<()> {
+        self.clear_buffer();
+        let mut ranked: Vec<(usize, u32)> =
+            totals_ms.iter().copied().enumerate().filter(|&(_, ms)| ms > 0).collect();
+        ranked.sort_by_key(|&(_, ms)| std::cmp::Reverse(ms));
+        let lines: Vec<String> = ranked
+            .iter()
+            .map(|&(index, ms)| {
+                let name = ActivityClass::from_index(index).display_name();
+                format!("{:<12}{:>6}", name, dwell::format_duration(ms))
+            })
+            .collect();
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        self.draw_text_block(&lines);
+        self.present()
+    }
+
+    /// Hidden debug/stats screen (triple-click).
+    pub fn show_stats(
+        &mut self,
+        uptime_s: u32,
+        steps: u32,
+        battery_pct: f32,
+        free_heap: u32,
+    ) -> anyhow::Result<()> {
+        self.clear_buffer();
+        let lines = [
+            format!("up    {}:{:02}:{:02}", uptime_s / 3600, uptime_s / 60 % 60, uptime_s % 60),
+            format!("steps {}", steps),
+            format!("batt  {:.0}%", battery_pct),
+            format!("heap  {} B", free_heap),
+        ];
+        self.draw_text_block(&lines.each_ref().map(String::as_str));
+        self.present()
+    }
+
+    /// Runtime hardware check: device probes, live IMU reading, battery
+    /// voltage, die temperature, uptime and inference latency.
+    pub fn show_diagnostics(
+        &mut self,
+        oled_ok: bool,
+        imu_ok: bool,
+        state: &SystemState,
+        uptime_s: u32,
+    ) -> anyhow::Result<()> {
+        self.clear_buffer();
+        let strings = i18n::strings();
+        let ok = |v: bool| if v { strings.ok } else { strings.fail };
+        let or_dash = |v: Option<f32>, unit: &str| match v {
+            Some(v) => format!("{:.2} {}", v, unit),
+            None => "--".into(),
+        };
+
+        let lines = [
+            format!("OLED {}  IMU {}", ok(oled_ok), ok(imu_ok)),
+            match state.accel_g {
+                Some([x, y, z]) => format!("g {:5.2} {:5.2} {:5.2}", x, y, z),
+                None => "g --".into(),
+            },
+            format!("batt {}", or_dash(state.battery_v, "V")),
+            format!("temp {}", or_dash(state.temp_c, "C")),
+            format!(
+                "up {}:{:02}:{:02} ai {}",
+                uptime_s / 3600,
+                uptime_s / 60 % 60,
+                uptime_s % 60,
+                match state.inference_us {
+                    Some(us) => format!("{}ms", us / 1000),
+                    None => "--".into(),
+                }
+            ),
+        ];
+        self.draw_text_block(&lines.each_ref().map(String::as_str));
+        self.present()
+    }
+
+    /// IMU zeroing screen: a large countdown under the instruction, then
+    /// "hold still" while measuring, then the outcome.
+    pub fn show_calibration(&mut self, view: CalibrationView) -> anyhow::Result<()> {
+        self.clear_buffer();
+        let strings = i18n::strings();
+        match view {
+            CalibrationView::Countdown(seconds) => {
+                let hint = strings.zero_lay_flat;
+                self.draw_text(FontSize::Small.centered_x(hint), 8, hint, FontSize::Small);
+                let count = seconds.to_string();
+                self.draw_text(FontSize::Large.centered_x(&count), 28, &count, FontSize::Large);
+            }
+            CalibrationView::Measuring => {
+                self.draw_text_block(&[strings.zero_measuring, "", strings.zero_hold_still])
+            }
+            CalibrationView::Done(true) => {
+                self.draw_text_block(&[strings.zero_done, "", strings.zero_kept])
+            }
+            CalibrationView::Done(false) => {
+                self.draw_text_block(&[
+                    strings.zero_failed,
+                    "",
+                    strings.zero_moved,
+                    strings.zero_no_imu,
+                ])
+            }
+        }
+        self.present()
+    }
+
+    /// "Powering off" ring that shrinks as `progress` goes 0.0 → 1.0.
+    pub fn show_hold_progress(&mut self, progress: f32) -> anyhow::Result<()> {
+        self.clear_buffer();
+        let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
+
+        let remaining = 1.0 - progress.clamp(0.0, 1.0);
+        Arc::with_center(Point::new(64, 26), 36, (-90.0f32).deg(), (360.0 * remaining).deg())
+            .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 3))
+            .draw(self)
+            .unwrap();
+        let label = i18n::strings().powering_off;
+        Text::with_alignment(label, Point::new(64, 60), style, Alignment::Center)
+            .draw(self)
+            .unwrap();
+
+        self.present()
+    }
+
+    /// Fall alert screen, held until the user acknowledges with the button.
+    /// Scrolls: call again each `SCROLL_STEP_INTERVAL_MS` to advance it.
+    pub fn show_fall_alert(&mut self) -> anyhow::Result<()> {
+        self.show_scrolling_text(i18n::strings().fall_alert)
     }
 
     /// Boot check result screen.
@@ -297,21 +835,155 @@ impl OledDisplay {
         self.clear_buffer();
         let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
 
-        let oled_msg = if oled_ok { "OLED:  OK" } else { "OLED:  FAIL" };
-        let imu_msg = if imu_ok { "MPU6050: OK" } else { "MPU6050: FAIL" };
+        let strings = i18n::strings();
+        let ok = |v: bool| if v { strings.ok } else { strings.fail };
+        let oled_msg = format!("OLED:  {}", ok(oled_ok));
+        let imu_msg = format!("MPU6050: {}", ok(imu_ok));
+
+        Text::new(&oled_msg, Point::new(20, 24), style).draw(self).unwrap();
+        Text::new(&imu_msg, Point::new(20, 40), style).draw(self).unwrap();
+
+        self.present()
+    }
+
+    /// Training-data capture screen: "REC" plus the running sample count.
+    #[cfg(feature = "capture")]
+    pub fn show_capture(&mut self, samples: u32) -> anyhow::Result<()> {
+        self.clear_buffer();
+        let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
+
+        // Filled "recording" marker next to the label
+        Rectangle::new(Point::new(42, 19), Size::new(6, 6))
+            .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+            .draw(self)
+            .unwrap();
+        Text::new("REC", Point::new(54, 26), style).draw(self).unwrap();
 
-        Text::new(oled_msg, Point::new(20, 24), style).draw(self).unwrap();
-        Text::new(imu_msg, Point::new(20, 40), style).draw(self).unwrap();
+        let line = format!("{} samples", samples);
+        Text::with_alignment(&line, Point::new(64, 46), style, Alignment::Center)
+            .draw(self)
+            .unwrap();
 
-        self.flush()
+        self.present()
+    }
+
+    // -- drawing primitives -------------------------------------------------
+
+    /// Draw `text` into the back buffer with the top-left corner of its
+    /// first character at (`x`, `y`).  Anything off the screen is clipped.
+    pub fn draw_text(&mut self, x: i32, y: i32, text: &str, size: FontSize) {
+        let style = MonoTextStyle::new(size.font(), BinaryColor::On);
+        Text::with_baseline(text, Point::new(x, y), style, Baseline::Top)
+            .draw(self)
+            .unwrap(); // draw to buffer never fails
+    }
+
+    /// Draw lines of small text, left-aligned and vertically centred, each
+    /// word-wrapped to the screen width.  An empty string is a blank line.
+    /// If the wrapped text needs more rows than fit (five), the last row
+    /// shown ends in "...".
+    pub fn draw_text_block(&mut self, lines: &[&str]) {
+        let size = FontSize::Small;
+        let glyph = size.glyph_size();
+        let cols = ((SCREEN_WIDTH - 2 * TEXT_BLOCK_MARGIN_PX) / glyph.width) as usize;
+        let pitch = glyph.height + TEXT_BLOCK_LINE_GAP_PX;
+        let max_rows = ((SCREEN_HEIGHT + TEXT_BLOCK_LINE_GAP_PX) / pitch) as usize;
+
+        let mut rows: Vec<String> = lines.iter().flat_map(|line| wrap(line, cols)).collect();
+        if rows.len() > max_rows {
+            rows.truncate(max_rows);
+            let last = &mut rows[max_rows - 1];
+            let keep = last.chars().count().min(cols - 3);
+            *last = last.chars().take(keep).collect::<String>() + "...";
+        }
+
+        let height = (rows.len() as u32 * pitch).saturating_sub(TEXT_BLOCK_LINE_GAP_PX);
+        let top = (SCREEN_HEIGHT.saturating_sub(height) / 2) as i32;
+        for (i, row) in rows.iter().enumerate() {
+            let y = top + (i as u32 * pitch) as i32;
+            self.draw_text(TEXT_BLOCK_MARGIN_PX as i32, y, row, size);
+        }
     }
 
     // -- private helpers ----------------------------------------------------
 
-    fn draw_battery(&mut self, level: f32) {
+    /// Show the battery icon as charging (fill sweeping up to full) or not.
+    /// Takes effect on the next screen that draws the icon.
+    pub fn set_charging(&mut self, charging: bool) {
+        self.charge_frame = charging.then_some(0);
+    }
+
+    /// Mark the IMU as lost (or back).  Like the charging state, it shows on
+    /// the next screen that draws the battery icon.
+    pub fn set_sensor_lost(&mut self, lost: bool) {
+        self.sensor_lost = lost;
+    }
+
+    /// How full the AI feature window is; shows on the next activity screen.
+    pub fn set_window_progress(&mut self, progress: f32) {
+        self.window_progress = progress.clamp(0.0, 1.0);
+    }
+
+    /// Step the charging animation; redraw the screen to show it.
+    pub fn advance_charge_animation(&mut self) {
+        if let Some(frame) = self.charge_frame.as_mut() {
+            *frame = (*frame + 1) % 4;
+        }
+    }
+
+    /// Thin horizontal bar, full width = 100 % confidence.
+    fn draw_confidence_bar(&mut self, confidence: f32) {
+        let outline = PrimitiveStyle::with_stroke(BinaryColor::On, 1);
+        let filled = PrimitiveStyle::with_fill(BinaryColor::On);
+
+        Rectangle::new(Point::new(24, 43), Size::new(80, 5))
+            .into_styled(outline)
+            .draw(self)
+            .unwrap();
+
+        let fill_width = (78.0 * confidence.clamp(0.0, 1.0)) as u32;
+        if fill_width > 0 {
+            Rectangle::new(Point::new(25, 44), Size::new(fill_width, 3))
+                .into_styled(filled)
+                .draw(self)
+                .unwrap();
+        }
+    }
+
+    /// 2×2 dot under the confidence bar that travels left to right as the
+    /// feature window fills.
+    fn draw_window_progress(&mut self) {
+        if self.window_progress <= 0.0 {
+            return;
+        }
+        let x = 25 + (76.0 * self.window_progress) as i32;
+        Rectangle::new(Point::new(x, 49), Size::new(2, 2))
+            .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+            .draw(self)
+            .unwrap();
+    }
+
+    /// Battery glyph in the top-right corner with the integer percent to its
+    /// left.  Below `BATTERY_LOW_PCT` the fill gives way to a "!" so a low
+    /// battery stands out; while charging the fill animates instead.
+    fn draw_battery_indicator(&mut self, level: f32) {
         let clamped = level.clamp(0.0, 100.0);
         let outline = PrimitiveStyle::with_stroke(BinaryColor::On, 1);
         let filled = PrimitiveStyle::with_fill(BinaryColor::On);
+        let text_style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
+
+        // Sensor-lost marker (top-left)
+        if self.sensor_lost {
+            Text::new(i18n::strings().sensor_lost, Point::new(0, 8), text_style)
+                .draw(self)
+                .unwrap();
+        }
+
+        // Percentage, right-aligned against the icon
+        let percent = format!("{:.0}%", clamped);
+        Text::with_alignment(&percent, Point::new(105, 8), text_style, Alignment::Right)
+            .draw(self)
+            .unwrap();
 
         // Battery outline (top-right)
         Rectangle::new(Point::new(108, 0), Size::new(18, 10))
@@ -325,8 +997,17 @@ impl OledDisplay {
             .draw(self)
             .unwrap();
 
-        // Fill bar proportional to charge
-        let fill_width = ((14.0 * clamped / 100.0) as u32).max(1);
+        if clamped < BATTERY_LOW_PCT && self.charge_frame.is_none() {
+            Text::new("!", Point::new(114, 8), text_style).draw(self).unwrap();
+            return;
+        }
+
+        // Fill bar proportional to charge; while charging it sweeps from
+        // the current level up to full.
+        let mut fill_width = ((14.0 * clamped / 100.0) as u32).max(1);
+        if let Some(frame) = self.charge_frame {
+            fill_width += (14 - fill_width) * frame as u32 / 3;
+        }
         Rectangle::new(Point::new(110, 2), Size::new(fill_width, 6))
             .into_styled(filled)
             .draw(self)
@@ -334,6 +1015,34 @@ impl OledDisplay {
     }
 }
 
+/// Split `text` into rows of at most `cols` characters, breaking at spaces
+/// where possible and mid-word only when a word is longer than a row.  An
+/// empty `text` is one empty row.
+fn wrap(text: &str, cols: usize) -> Vec<String> {
+    let mut rows = Vec::new();
+    let mut row = String::new();
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        let row_len = row.chars().count();
+        if row_len > 0 && row_len + 1 + word.len() <= cols {
+            row.push(' ');
+            row.extend(word);
+            continue;
+        }
+        if row_len > 0 {
+            rows.push(std::mem::take(&mut row));
+        }
+        while word.len() > cols {
+            rows.push(word.drain(..cols).collect());
+        }
+        row.extend(word);
+    }
+    if !row.is_empty() || rows.is_empty() {
+        rows.push(row);
+    }
+    rows
+}
+
 // ---------------------------------------------------------------------------
 // embedded_graphics::DrawTarget implementation
 // ---------------------------------------------------------------------------
@@ -354,11 +1063,12 @@ impl DrawTarget for OledDisplay {
                 let page = yu / 8;
                 let bit = yu % 8;
                 let idx = page * SCREEN_WIDTH as usize + xu;
-                if color == BinaryColor::On {
-                    self.buffer[idx] |= 1 << bit;
+                let value = if color == BinaryColor::On {
+                    self.buffer[idx] | 1 << bit
                 } else {
-                    self.buffer[idx] &= !(1 << bit);
-                }
+                    self.buffer[idx] & !(1 << bit)
+                };
+                self.set_byte(idx, value);
             }
         }
         Ok(())
@@ -1,30 +1,320 @@
 // PlastiWatch V2 — Haptic Motor Driver
 //
-// Simple GPIO-driven vibration motor.
+// Vibration motor driven by an LEDC PWM channel, so strength is adjustable:
+// `intensity` 0–255 maps linearly onto the duty cycle.  A gentle click
+// confirmation and an urgent fall alert can then differ in strength, not just
+// length.
+//
+// `HapticDriver` buzzes on the caller's thread (fine at boot).  For the UI
+// loop, `HapticDriver::spawn` moves the motor onto its own thread and returns
+// a `HapticHandle` whose `trigger()` / `buzz()` / `play()` return immediately;
+// the haptic thread sequences the pulses.
+//
+// Feedback is described by `HapticPattern`s — on/off interval tables — rather
+// than sleeps at each call site.  A newer request interrupts the one playing
+// if its priority is the same or higher, so a click can't cut short an SOS.
+//
+// The motor's current draw dips the battery rail, so `motor_active()` lets
+// the power task avoid sampling the battery mid-buzz.  Near the end of charge
+// that dip can brown out the MCU: after `set_low_battery(true)` every pulse is
+// capped at `HAPTIC_LOW_BATTERY_INTENSITY` and `HAPTIC_LOW_BATTERY_MAX_ON_MS`
+// (patterns keep their rhythm, just shorter and weaker).  The UI task sets it
+// from the battery level.
 
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+use esp_idf_hal::ledc::LedcDriver;
+
+use crate::config::*;
+
+/// Whether the motor is currently being driven.
+static MOTOR_ON: AtomicBool = AtomicBool::new(false);
+
+/// Whether pulses are being limited for a low battery.
+static LOW_BATTERY: AtomicBool = AtomicBool::new(false);
+
+/// Whether the haptic motor is running right now.
+pub fn motor_active() -> bool {
+    MOTOR_ON.load(Ordering::Relaxed)
+}
+
+/// Limit (or stop limiting) pulse strength and length; takes effect from the
+/// next pulse.
+pub fn set_low_battery(low: bool) {
+    if LOW_BATTERY.swap(low, Ordering::Relaxed) != low {
+        log::info!("Haptics {}", if low { "limited (low battery)" } else { "at full strength" });
+    }
+}
+
+/// `(on, intensity)` for one pulse after any low-battery limits.
+fn limited(on: Duration, intensity: u8) -> (Duration, u8) {
+    if LOW_BATTERY.load(Ordering::Relaxed) {
+        (
+            on.min(Duration::from_millis(HAPTIC_LOW_BATTERY_MAX_ON_MS)),
+            intensity.min(HAPTIC_LOW_BATTERY_INTENSITY),
+        )
+    } else {
+        (on, intensity)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Patterns
+// ---------------------------------------------------------------------------
+
+/// Named vibration sequences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HapticPattern {
+    SingleTap,
+    DoubleTap,
+    LongBuzz,
+    SosMorse,
+    Heartbeat,
+}
+
+impl HapticPattern {
+    /// `(on ms, off ms)` steps played in order.
+    fn steps(self) -> &'static [(u16, u16)] {
+        // Morse timing: dot = 100 ms, dash = 300 ms, letter gap = 300 ms.
+        const SOS: [(u16, u16); 9] = [
+            (100, 100), (100, 100), (100, 300), // S
+            (300, 100), (300, 100), (300, 300), // O
+            (100, 100), (100, 100), (100, 0),   // S
+        ];
+
+        match self {
+            Self::SingleTap => &[(HAPTIC_CLICK_MS as u16, 0)],
+            Self::DoubleTap => &[(50, 100), (50, 0)],
+            Self::LongBuzz  => &[(500, 0)],
+            Self::SosMorse  => &SOS,
+            Self::Heartbeat => &[(60, 120), (100, 0)],
+        }
+    }
+
+    /// Default strength.
+    pub fn intensity(self) -> u8 {
+        match self {
+            Self::SingleTap | Self::DoubleTap | Self::Heartbeat => HAPTIC_INTENSITY_MEDIUM,
+            Self::LongBuzz | Self::SosMorse => HAPTIC_INTENSITY_STRONG,
+        }
+    }
+
+    /// Patterns only interrupt those of the same or lower priority.
+    fn priority(self) -> u8 {
+        match self {
+            Self::SingleTap | Self::DoubleTap => 0,
+            Self::LongBuzz | Self::Heartbeat => 1,
+            Self::SosMorse => 2,
+        }
+    }
+}
+
+/// What the haptic thread has been asked to play.
+#[derive(Clone, Copy)]
+enum Source {
+    Pulse(Duration),
+    Pattern(HapticPattern),
+    /// Silence the motor, interrupting anything.
+    Stop,
+}
+
+impl Source {
+    /// `(on, off)` for step `index`, or `None` past the end.
+    fn step(self, index: usize) -> Option<(Duration, Duration)> {
+        match self {
+            Self::Pulse(d) => (index == 0).then_some((d, Duration::ZERO)),
+            Self::Pattern(p) => p.steps().get(index).map(|&(on, off)| {
+                (Duration::from_millis(on as u64), Duration::from_millis(off as u64))
+            }),
+            Self::Stop => None,
+        }
+    }
+
+    fn priority(self) -> u8 {
+        match self {
+            Self::Pulse(_) => 0,
+            Self::Pattern(p) => p.priority(),
+            Self::Stop => u8::MAX,
+        }
+    }
+}
+
+/// Progress through a `Source` on the haptic thread.
+struct Playback {
+    source: Source,
+    intensity: u8,
+    index: usize,
+    on: bool,
+    next_at: Instant,
+}
 
-use esp_idf_hal::gpio::{Output, PinDriver};
+// ---------------------------------------------------------------------------
+// Driver
+// ---------------------------------------------------------------------------
 
 pub struct HapticDriver<'d> {
-    pin: PinDriver<'d, esp_idf_hal::gpio::AnyOutputPin, Output>,
+    pwm: LedcDriver<'d>,
 }
 
 impl<'d> HapticDriver<'d> {
-    pub fn new(pin: PinDriver<'d, esp_idf_hal::gpio::AnyOutputPin, Output>) -> Self {
-        Self { pin }
+    pub fn new(pwm: LedcDriver<'d>) -> Self {
+        let mut driver = Self { pwm };
+        driver.set_intensity(0);
+        driver
     }
 
-    /// Short 50 ms vibration pulse — tactile feedback for button clicks.
+    /// Short 50 ms pulse at medium strength — tactile feedback for button clicks.
     pub fn trigger(&mut self) {
-        self.buzz(Duration::from_millis(50));
+        self.play(HapticPattern::SingleTap);
     }
 
-    /// Vibrate for a custom duration (blocks the calling thread).
+    /// Vibrate at full strength for a custom duration (blocks the calling thread).
     pub fn buzz(&mut self, duration: Duration) {
-        let _ = self.pin.set_high();
+        self.buzz_intensity(duration, HAPTIC_INTENSITY_STRONG);
+    }
+
+    /// Vibrate at `intensity` (0 = off, 255 = full) for `duration` (blocks the
+    /// calling thread).
+    pub fn buzz_intensity(&mut self, duration: Duration, intensity: u8) {
+        let (duration, intensity) = limited(duration, intensity);
+        self.set_intensity(intensity);
         thread::sleep(duration);
-        let _ = self.pin.set_low();
+        self.set_intensity(0);
+    }
+
+    /// Play a pattern to completion (blocks the calling thread).
+    pub fn play(&mut self, pattern: HapticPattern) {
+        for &(on, off) in pattern.steps() {
+            self.buzz_intensity(Duration::from_millis(on as u64), pattern.intensity());
+            thread::sleep(Duration::from_millis(off as u64));
+        }
+    }
+
+    fn set_intensity(&mut self, intensity: u8) {
+        let duty = self.pwm.get_max_duty() * intensity as u32 / u8::MAX as u32;
+        let _ = self.pwm.set_duty(duty);
+        MOTOR_ON.store(intensity > 0, Ordering::Relaxed);
+    }
+}
+
+impl HapticDriver<'static> {
+    /// Hand the motor to a dedicated thread and return a non-blocking handle.
+    pub fn spawn(mut self) -> anyhow::Result<HapticHandle> {
+        let (tx, rx) = mpsc::channel();
+        thread::Builder::new()
+            .name("haptic".into())
+            .stack_size(STACK_HAPTIC)
+            .spawn(move || self.run(rx))?;
+        Ok(HapticHandle { tx })
+    }
+
+    /// Haptic thread: step through the current playback, switching the motor
+    /// on and off at each deadline, while staying responsive to new requests.
+    fn run(&mut self, rx: Receiver<(Source, u8)>) {
+        let mut current: Option<Playback> = None;
+
+        loop {
+            let next = match &current {
+                Some(p) => rx.recv_timeout(p.next_at.saturating_duration_since(Instant::now())),
+                None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+
+            match next {
+                Ok((source, intensity)) => {
+                    let preempts = current
+                        .as_ref()
+                        .map_or(true, |p| source.priority() >= p.source.priority());
+                    if preempts {
+                        current = self.start(source, intensity);
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    if let Some(p) = current.take() {
+                        current = self.advance(p);
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.set_intensity(0);
+                    return;
+                }
+            }
+        }
+    }
+
+    fn start(&mut self, source: Source, intensity: u8) -> Option<Playback> {
+        self.step_on(Playback {
+            source,
+            intensity,
+            index: 0,
+            on: false,
+            next_at: Instant::now(),
+        })
+    }
+
+    /// Finish the current on/off phase; `None` once the source is exhausted.
+    fn advance(&mut self, mut p: Playback) -> Option<Playback> {
+        if p.on {
+            self.set_intensity(0);
+            let (_, off) = p.source.step(p.index)?;
+            p.on = false;
+            p.next_at = Instant::now() + off;
+            Some(p)
+        } else {
+            p.index += 1;
+            self.step_on(p)
+        }
+    }
+
+    fn step_on(&mut self, mut p: Playback) -> Option<Playback> {
+        let Some((on, _)) = p.source.step(p.index) else {
+            self.set_intensity(0);
+            return None;
+        };
+        let (on, intensity) = limited(on, p.intensity);
+        self.set_intensity(intensity);
+        p.on = true;
+        p.next_at = Instant::now() + on;
+        Some(p)
+    }
+}
+
+/// Non-blocking front end for a motor running on its own thread.
+#[derive(Clone)]
+pub struct HapticHandle {
+    tx: Sender<(Source, u8)>,
+}
+
+impl HapticHandle {
+    /// Short 50 ms pulse at medium strength — tactile feedback for button clicks.
+    pub fn trigger(&self) {
+        self.play(HapticPattern::SingleTap);
+    }
+
+    /// Vibrate at full strength for a custom duration; returns immediately.
+    pub fn buzz(&self, duration: Duration) {
+        self.buzz_intensity(duration, HAPTIC_INTENSITY_STRONG);
+    }
+
+    /// Vibrate at `intensity` (0 = off, 255 = full); returns immediately.
+    pub fn buzz_intensity(&self, duration: Duration, intensity: u8) {
+        let _ = self.tx.send((Source::Pulse(duration), intensity));
+    }
+
+    /// Play a pattern at its default strength; returns immediately.
+    pub fn play(&self, pattern: HapticPattern) {
+        self.play_intensity(pattern, pattern.intensity());
+    }
+
+    /// Play a pattern at `intensity`; returns immediately.
+    pub fn play_intensity(&self, pattern: HapticPattern, intensity: u8) {
+        let _ = self.tx.send((Source::Pattern(pattern), intensity));
+    }
+
+    /// Cut off whatever is playing, regardless of priority.
+    pub fn stop(&self) {
+        let _ = self.tx.send((Source::Stop, 0));
     }
 }
@@ -4,8 +4,11 @@
 // Avoids external crate version conflicts with esp-idf-hal.
 
 use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
 
 use esp_idf_hal::i2c::I2cDriver;
+use esp_idf_sys::EspError;
 
 use crate::config::*;
 use crate::events::SensorData;
@@ -15,71 +18,455 @@ pub type SharedBus = &'static Mutex<I2cDriver<'static>>;
 
 // MPU6050 register addresses
 const REG_PWR_MGMT_1: u8 = 0x6B;
+const REG_PWR_MGMT_2: u8 = 0x6C;
 const REG_CONFIG: u8 = 0x1A;
 const REG_GYRO_CONFIG: u8 = 0x1B;
 const REG_ACCEL_CONFIG: u8 = 0x1C;
+const REG_MOT_THR: u8 = 0x1F;
+const REG_MOT_DUR: u8 = 0x20;
+const REG_INT_PIN_CFG: u8 = 0x37;
+const REG_INT_ENABLE: u8 = 0x38;
+const REG_INT_STATUS: u8 = 0x3A;
 const REG_ACCEL_XOUT_H: u8 = 0x3B; // Start of 14-byte sensor burst
+const REG_TEMP_OUT_H: u8 = 0x41;
+const REG_MOT_DETECT_CTRL: u8 = 0x69;
 const REG_WHO_AM_I: u8 = 0x75;
-const WHO_AM_I_EXPECTED: u8 = 0x68;
+const WHO_AM_I_EXPECTED: u8 = 0x68; // Whatever AD0 is strapped to
+
+/// Accelerometer full-scale range (`ACCEL_CONFIG.AFS_SEL`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AccelRange {
+    G2,
+    G4,
+    #[default]
+    G8,
+    G16,
+}
+
+impl AccelRange {
+    fn register_value(self) -> u8 {
+        match self {
+            Self::G2  => 0x00,
+            Self::G4  => 0x08,
+            Self::G8  => 0x10,
+            Self::G16 => 0x18,
+        }
+    }
+
+    /// Sensitivity in LSB/g.
+    pub fn scale(self) -> f32 {
+        match self {
+            Self::G2  => 16384.0,
+            Self::G4  => 8192.0,
+            Self::G8  => 4096.0,
+            Self::G16 => 2048.0,
+        }
+    }
+
+    /// Full-scale value in g.
+    pub fn max_g(self) -> u32 {
+        match self {
+            Self::G2  => 2,
+            Self::G4  => 4,
+            Self::G8  => 8,
+            Self::G16 => 16,
+        }
+    }
+}
+
+/// Gyroscope full-scale range (`GYRO_CONFIG.FS_SEL`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GyroRange {
+    Dps250,
+    #[default]
+    Dps500,
+    Dps1000,
+    Dps2000,
+}
+
+impl GyroRange {
+    fn register_value(self) -> u8 {
+        match self {
+            Self::Dps250  => 0x00,
+            Self::Dps500  => 0x08,
+            Self::Dps1000 => 0x10,
+            Self::Dps2000 => 0x18,
+        }
+    }
+
+    /// Sensitivity in LSB/°/s.
+    pub fn scale(self) -> f32 {
+        match self {
+            Self::Dps250  => 131.0,
+            Self::Dps500  => 65.5,
+            Self::Dps1000 => 32.8,
+            Self::Dps2000 => 16.4,
+        }
+    }
+
+    /// Full-scale value in °/s.
+    pub fn max_dps(self) -> u32 {
+        match self {
+            Self::Dps250  => 250,
+            Self::Dps500  => 500,
+            Self::Dps1000 => 1000,
+            Self::Dps2000 => 2000,
+        }
+    }
+}
+
+/// Accelerometer wake-up rate in low-power cycle mode
+/// (`PWR_MGMT_2.LP_WAKE_CTRL`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CycleRate {
+    Hz1_25,
+    Hz5,
+    Hz20,
+    Hz40,
+}
+
+impl CycleRate {
+    fn register_value(self) -> u8 {
+        match self {
+            Self::Hz1_25 => 0x00,
+            Self::Hz5    => 0x40,
+            Self::Hz20   => 0x80,
+            Self::Hz40   => 0xC0,
+        }
+    }
+}
+
+/// Per-axis zero-point offsets subtracted from every reading.
+/// Accelerometer offsets in g, gyro offsets in °/s.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CalibrationOffsets {
+    pub ax: f32,
+    pub ay: f32,
+    pub az: f32,
+    pub gx: f32,
+    pub gy: f32,
+    pub gz: f32,
+}
 
 pub struct Mpu6050 {
     bus: SharedBus,
+    accel_range: AccelRange,
+    gyro_range: GyroRange,
+    offsets: CalibrationOffsets,
 }
 
 impl Mpu6050 {
+    /// Driver using the default ranges (±8 g, ±500 °/s).
     pub fn new(bus: SharedBus) -> Self {
-        Self { bus }
+        Self::with_ranges(bus, AccelRange::default(), GyroRange::default())
+    }
+
+    /// Driver using explicit full-scale ranges, applied by [`Self::init`].
+    /// E.g. ±16 g for fall detection, ±2 g for fine gesture work.
+    pub fn with_ranges(bus: SharedBus, accel_range: AccelRange, gyro_range: GyroRange) -> Self {
+        Self {
+            bus,
+            accel_range,
+            gyro_range,
+            offsets: CalibrationOffsets::default(),
+        }
+    }
+
+    /// Currently applied calibration offsets (e.g. to persist across boots).
+    pub fn offsets(&self) -> CalibrationOffsets {
+        self.offsets
+    }
+
+    /// Apply previously stored calibration offsets.
+    pub fn set_offsets(&mut self, offsets: CalibrationOffsets) {
+        self.offsets = offsets;
+    }
+
+    /// Measure and apply zero-point offsets by averaging `samples` readings
+    /// taken at the normal sample rate.
+    ///
+    /// The watch must lie still, face up (+Z towards the sky), so the
+    /// accelerometer should read (0, 0, +1 g) and the gyro (0, 0, 0).  If any
+    /// axis varies more than the configured limit the device was moving and an
+    /// error is returned; the existing offsets are left untouched.
+    pub fn calibrate(&mut self, samples: usize) -> anyhow::Result<CalibrationOffsets> {
+        if samples < 2 {
+            anyhow::bail!("calibration needs at least 2 samples");
+        }
+
+        let mut sum = [0.0f32; 6];
+        let mut sum_sq = [0.0f32; 6];
+        for _ in 0..samples {
+            let d = self.read_uncalibrated()?;
+            for (i, v) in [d.ax, d.ay, d.az, d.gx, d.gy, d.gz].into_iter().enumerate() {
+                sum[i] += v;
+                sum_sq[i] += v * v;
+            }
+            thread::sleep(Duration::from_millis(SENSOR_SAMPLE_INTERVAL_MS));
+        }
+
+        let n = samples as f32;
+        let mean = sum.map(|s| s / n);
+        for i in 0..6 {
+            let variance = sum_sq[i] / n - mean[i] * mean[i];
+            let limit = if i < 3 {
+                CALIBRATION_MAX_ACCEL_VARIANCE
+            } else {
+                CALIBRATION_MAX_GYRO_VARIANCE
+            };
+            if variance > limit {
+                anyhow::bail!(
+                    "calibration rejected: axis {} variance {:.5} exceeds {} (device moving?)",
+                    i,
+                    variance,
+                    limit
+                );
+            }
+        }
+
+        let offsets = CalibrationOffsets {
+            ax: mean[0],
+            ay: mean[1],
+            az: mean[2] - 1.0, // gravity
+            gx: mean[3],
+            gy: mean[4],
+            gz: mean[5],
+        };
+        self.offsets = offsets;
+        log::info!("MPU6050 calibrated: {:?}", offsets);
+        Ok(offsets)
+    }
+
+    /// Run a bus transaction, retrying transient failures (e.g. a NACK while
+    /// the OLED is mid-transfer) up to [`I2C_MAX_RETRIES`] times with a linear
+    /// backoff.  The bus lock is released between attempts.
+    fn with_retry<T>(
+        &self,
+        mut op: impl FnMut(&mut I2cDriver<'static>) -> Result<T, EspError>,
+    ) -> Result<T, EspError> {
+        let mut attempt = 0;
+        loop {
+            let result = op(&mut self.bus.lock().unwrap());
+            match result {
+                Ok(value) => return Ok(value),
+                Err(e) if attempt < I2C_MAX_RETRIES => {
+                    attempt += 1;
+                    log::debug!("IMU I2C error ({}) — retry {}/{}", e, attempt, I2C_MAX_RETRIES);
+                    thread::sleep(Duration::from_millis(I2C_RETRY_DELAY_MS * attempt as u64));
+                }
+                Err(e) => return Err(e),
+            }
+        }
     }
 
     /// Verify the device is reachable on the I2C bus.
     pub fn is_connected(&self) -> bool {
-        let mut bus = self.bus.lock().unwrap();
         let mut buf = [0u8; 1];
-        match bus.write_read(I2C_ADDR_MPU6050, &[REG_WHO_AM_I], &mut buf, I2C_TIMEOUT_TICKS) {
+        let result = self.with_retry(|bus| {
+            bus.write_read(I2C_ADDR_MPU6050, &[REG_WHO_AM_I], &mut buf, I2C_TIMEOUT_TICKS_IMU)
+        });
+        match result {
             Ok(()) => buf[0] == WHO_AM_I_EXPECTED,
             Err(_) => false,
         }
     }
 
-    /// Wake the sensor and configure accel (±8 g), gyro (±500 °/s), DLPF 21 Hz.
+    /// One short, unretried WHO_AM_I read: whether the IMU answers right now.
+    /// Holds the bus lock for at most `I2C_SCAN_TIMEOUT_TICKS`, so it can be
+    /// polled without starving the display.
+    pub fn probe(&self) -> bool {
+        let mut buf = [0u8; 1];
+        let result = self.bus.lock().unwrap().write_read(
+            I2C_ADDR_MPU6050,
+            &[REG_WHO_AM_I],
+            &mut buf,
+            I2C_SCAN_TIMEOUT_TICKS,
+        );
+        result.is_ok() && buf[0] == WHO_AM_I_EXPECTED
+    }
+
+    /// Wake the sensor and configure the accel / gyro ranges and DLPF 21 Hz.
     pub fn init(&self) -> anyhow::Result<()> {
         let mut bus = self.bus.lock().unwrap();
 
-        // Wake up (clear SLEEP bit)
-        bus.write(I2C_ADDR_MPU6050, &[REG_PWR_MGMT_1, 0x00], I2C_TIMEOUT_TICKS)?;
+        // Wake up (clear SLEEP and CYCLE) with every axis running
+        bus.write(I2C_ADDR_MPU6050, &[REG_PWR_MGMT_1, 0x00], I2C_TIMEOUT_TICKS_IMU)?;
+        bus.write(I2C_ADDR_MPU6050, &[REG_PWR_MGMT_2, 0x00], I2C_TIMEOUT_TICKS_IMU)?;
 
         // DLPF bandwidth 21 Hz
-        bus.write(I2C_ADDR_MPU6050, &[REG_CONFIG, 0x04], I2C_TIMEOUT_TICKS)?;
+        bus.write(I2C_ADDR_MPU6050, &[REG_CONFIG, 0x04], I2C_TIMEOUT_TICKS_IMU)?;
 
-        // Gyroscope: ±500 °/s
-        bus.write(I2C_ADDR_MPU6050, &[REG_GYRO_CONFIG, 0x08], I2C_TIMEOUT_TICKS)?;
+        // Gyroscope range
+        bus.write(
+            I2C_ADDR_MPU6050,
+            &[REG_GYRO_CONFIG, self.gyro_range.register_value()],
+            I2C_TIMEOUT_TICKS_IMU,
+        )?;
 
-        // Accelerometer: ±8 g
-        bus.write(I2C_ADDR_MPU6050, &[REG_ACCEL_CONFIG, 0x10], I2C_TIMEOUT_TICKS)?;
+        // Accelerometer range (also clears the motion-detect high-pass filter)
+        bus.write(
+            I2C_ADDR_MPU6050,
+            &[REG_ACCEL_CONFIG, self.accel_range.register_value()],
+            I2C_TIMEOUT_TICKS_IMU,
+        )?;
+
+        // Interrupts off — the motion-wake setup survives our deep sleep.
+        bus.write(I2C_ADDR_MPU6050, &[REG_INT_ENABLE, 0x00], I2C_TIMEOUT_TICKS_IMU)?;
 
-        log::info!("MPU6050 initialised (±8g, ±500°/s, DLPF 21Hz)");
+        log::info!(
+            "MPU6050 initialised (±{}g, ±{}°/s, DLPF 21Hz)",
+            self.accel_range.max_g(),
+            self.gyro_range.max_dps()
+        );
         Ok(())
     }
 
-    /// Burst-read all 6 axes and convert to physical units.
-    pub fn read_data(&self) -> anyhow::Result<SensorData> {
+    /// Enter low-power accelerometer cycle mode: the chip sleeps between
+    /// single accelerometer samples taken at `rate` (~10–110 µA instead of
+    /// ~3.9 mA).  The gyroscope is put in standby, so gyro axes read 0 until
+    /// [`Self::set_normal_mode`]; reads between wake-ups return the latest
+    /// sample.
+    pub fn set_low_power_mode(&self, rate: CycleRate) -> anyhow::Result<()> {
         let mut bus = self.bus.lock().unwrap();
-        let mut raw = [0u8; 14];
-        bus.write_read(
+        // Gyro X/Y/Z standby plus the wake-up rate.
+        bus.write(
             I2C_ADDR_MPU6050,
-            &[REG_ACCEL_XOUT_H],
-            &mut raw,
-            I2C_TIMEOUT_TICKS,
+            &[REG_PWR_MGMT_2, rate.register_value() | 0x07],
+            I2C_TIMEOUT_TICKS_IMU,
+        )?;
+        // CYCLE = 1, SLEEP = 0.
+        bus.write(I2C_ADDR_MPU6050, &[REG_PWR_MGMT_1, 0x20], I2C_TIMEOUT_TICKS_IMU)?;
+        log::info!("MPU6050 low-power cycle mode ({:?}, gyro off)", rate);
+        Ok(())
+    }
+
+    /// Leave cycle mode: accelerometer and gyroscope sample continuously.
+    pub fn set_normal_mode(&self) -> anyhow::Result<()> {
+        let mut bus = self.bus.lock().unwrap();
+        bus.write(I2C_ADDR_MPU6050, &[REG_PWR_MGMT_1, 0x00], I2C_TIMEOUT_TICKS_IMU)?;
+        bus.write(I2C_ADDR_MPU6050, &[REG_PWR_MGMT_2, 0x00], I2C_TIMEOUT_TICKS_IMU)?;
+        log::info!("MPU6050 normal mode");
+        Ok(())
+    }
+
+    /// Arm the motion-detect interrupt: INT goes high (and stays latched until
+    /// the next [`Self::init`]) once the high-passed acceleration exceeds
+    /// `threshold_mg` on any axis for `duration_ms`.  Used as a deep-sleep
+    /// wake source; the threshold resolution is 2 mg.
+    pub fn enable_motion_interrupt(&self, threshold_mg: u32, duration_ms: u8) -> anyhow::Result<()> {
+        let threshold = (threshold_mg / 2).clamp(1, 255) as u8;
+        let mut bus = self.bus.lock().unwrap();
+
+        // 5 Hz high-pass so gravity and slow tilts don't count as motion.
+        bus.write(
+            I2C_ADDR_MPU6050,
+            &[REG_ACCEL_CONFIG, self.accel_range.register_value() | 0x01],
+            I2C_TIMEOUT_TICKS_IMU,
         )?;
+        bus.write(I2C_ADDR_MPU6050, &[REG_MOT_THR, threshold], I2C_TIMEOUT_TICKS_IMU)?;
+        bus.write(I2C_ADDR_MPU6050, &[REG_MOT_DUR, duration_ms.max(1)], I2C_TIMEOUT_TICKS_IMU)?;
+        // Extra 1 ms accelerometer power-on delay before detection starts.
+        bus.write(I2C_ADDR_MPU6050, &[REG_MOT_DETECT_CTRL, 0x10], I2C_TIMEOUT_TICKS_IMU)?;
+        // Active high, push-pull, latched until INT_STATUS is read.
+        bus.write(I2C_ADDR_MPU6050, &[REG_INT_PIN_CFG, 0x20], I2C_TIMEOUT_TICKS_IMU)?;
+        bus.write(I2C_ADDR_MPU6050, &[REG_INT_ENABLE, 0x40], I2C_TIMEOUT_TICKS_IMU)?;
+
+        // Clear anything latched while arming, or we'd wake straight away.
+        let mut status = [0u8; 1];
+        bus.write_read(I2C_ADDR_MPU6050, &[REG_INT_STATUS], &mut status, I2C_TIMEOUT_TICKS_IMU)?;
+
+        log::info!(
+            "MPU6050 motion interrupt armed (>{} mg for {} ms)",
+            threshold as u32 * 2,
+            duration_ms
+        );
+        Ok(())
+    }
+
+    /// Read the 14-byte sensor burst (accel, temperature, gyro) starting at
+    /// `ACCEL_XOUT_H`.
+    fn read_burst(&self) -> anyhow::Result<[u8; 14]> {
+        let mut raw = [0u8; 14];
+        self.with_retry(|bus| {
+            bus.write_read(
+                I2C_ADDR_MPU6050,
+                &[REG_ACCEL_XOUT_H],
+                &mut raw,
+                I2C_TIMEOUT_TICKS_IMU,
+            )
+        })?;
+        Ok(raw)
+    }
+
+    /// Burst-read all 6 axes and return the raw big-endian counts
+    /// `[ax, ay, az, gx, gy, gz]`, unscaled and independent of the range
+    /// setting.  Useful for exporting training data off the device.
+    pub fn read_data_raw(&self) -> anyhow::Result<[i16; 6]> {
+        let raw = self.read_burst()?;
+        Ok(axes_from_burst(&raw))
+    }
+
+    /// Burst-read all 6 axes and the die temperature, converted to physical
+    /// units with the calibration offsets applied.
+    pub fn read_data(&self) -> anyhow::Result<SensorData> {
+        let mut data = self.read_uncalibrated()?;
+        let o = &self.offsets;
+        data.ax -= o.ax;
+        data.ay -= o.ay;
+        data.az -= o.az;
+        data.gx -= o.gx;
+        data.gy -= o.gy;
+        data.gz -= o.gz;
+        Ok(data)
+    }
+
+    /// Like [`Self::read_data`] but without the calibration offsets.
+    fn read_uncalibrated(&self) -> anyhow::Result<SensorData> {
+        let raw = self.read_burst()?;
+        let [ax, ay, az, gx, gy, gz] = axes_from_burst(&raw);
+        let accel_scale = self.accel_range.scale();
+        let gyro_scale = self.gyro_range.scale();
 
         Ok(SensorData {
-            ax: i16::from_be_bytes([raw[0], raw[1]]) as f32 / ACCEL_SCALE_8G,
-            ay: i16::from_be_bytes([raw[2], raw[3]]) as f32 / ACCEL_SCALE_8G,
-            az: i16::from_be_bytes([raw[4], raw[5]]) as f32 / ACCEL_SCALE_8G,
-            // raw[6..8] = temperature — skipped
-            gx: i16::from_be_bytes([raw[8], raw[9]]) as f32 / GYRO_SCALE_500,
-            gy: i16::from_be_bytes([raw[10], raw[11]]) as f32 / GYRO_SCALE_500,
-            gz: i16::from_be_bytes([raw[12], raw[13]]) as f32 / GYRO_SCALE_500,
+            ax: ax as f32 / accel_scale,
+            ay: ay as f32 / accel_scale,
+            az: az as f32 / accel_scale,
+            gx: gx as f32 / gyro_scale,
+            gy: gy as f32 / gyro_scale,
+            gz: gz as f32 / gyro_scale,
+            temp_c: temp_from_raw(i16::from_be_bytes([raw[6], raw[7]])),
+            timestamp_ms: crate::now_ms(),
         })
     }
+
+    /// Read only the die temperature (°C).
+    pub fn read_temperature(&self) -> anyhow::Result<f32> {
+        let mut bus = self.bus.lock().unwrap();
+        let mut raw = [0u8; 2];
+        bus.write_read(
+            I2C_ADDR_MPU6050,
+            &[REG_TEMP_OUT_H],
+            &mut raw,
+            I2C_TIMEOUT_TICKS_IMU,
+        )?;
+        Ok(temp_from_raw(i16::from_be_bytes(raw)))
+    }
+}
+
+/// Split a sensor burst into `[ax, ay, az, gx, gy, gz]` (bytes 6..8 are the
+/// temperature and are skipped).
+fn axes_from_burst(raw: &[u8; 14]) -> [i16; 6] {
+    [
+        i16::from_be_bytes([raw[0], raw[1]]),
+        i16::from_be_bytes([raw[2], raw[3]]),
+        i16::from_be_bytes([raw[4], raw[5]]),
+        i16::from_be_bytes([raw[8], raw[9]]),
+        i16::from_be_bytes([raw[10], raw[11]]),
+        i16::from_be_bytes([raw[12], raw[13]]),
+    ]
+}
+
+/// Datasheet conversion: `temp_c = raw / 340 + 36.53`.
+fn temp_from_raw(raw: i16) -> f32 {
+    raw as f32 / 340.0 + 36.53
 }
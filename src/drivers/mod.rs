@@ -1,3 +1,5 @@
+pub mod bus;
 pub mod display;
 pub mod imu;
 pub mod haptic;
+pub mod rtc;
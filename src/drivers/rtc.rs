@@ -0,0 +1,106 @@
+// PlastiWatch V2 — DS3231 Real-Time Clock Driver (optional)
+//
+// Battery-backed RTC on the shared I2C bus.  Only the timekeeping registers
+// (0x00–0x06, BCD) are used; alarms and the square-wave output are left at
+// their power-on defaults.  Time is exchanged as Unix seconds (UTC) so the
+// `clock` module can hand it straight to the system clock.
+//
+// The DS3231's address (0x68) collides with the MPU6050's default, so the
+// IMU's AD0 pin must be pulled high (0x69) on boards that fit one.
+
+use crate::config::*;
+use crate::drivers::imu::SharedBus;
+
+const REG_SECONDS: u8 = 0x00;
+
+pub struct Ds3231 {
+    bus: SharedBus,
+}
+
+impl Ds3231 {
+    pub fn new(bus: SharedBus) -> Self {
+        Self { bus }
+    }
+
+    /// Whether the chip ACKs a read of the seconds register.
+    pub fn is_connected(&self) -> bool {
+        let mut buf = [0u8; 1];
+        let mut bus = self.bus.lock().unwrap();
+        bus.write_read(I2C_ADDR_DS3231, &[REG_SECONDS], &mut buf, I2C_TIMEOUT_TICKS_RTC)
+            .is_ok()
+    }
+
+    /// Current time as Unix seconds (the RTC is kept in UTC).
+    pub fn read_unix(&self) -> anyhow::Result<i64> {
+        let mut regs = [0u8; 7];
+        {
+            let mut bus = self.bus.lock().unwrap();
+            bus.write_read(I2C_ADDR_DS3231, &[REG_SECONDS], &mut regs, I2C_TIMEOUT_TICKS_RTC)?;
+        }
+
+        let second = bcd_to_bin(regs[0] & 0x7F) as i64;
+        let minute = bcd_to_bin(regs[1] & 0x7F) as i64;
+        let hour = bcd_to_bin(regs[2] & 0x3F) as i64; // 24-hour mode
+        let day = bcd_to_bin(regs[4] & 0x3F) as u32;
+        let month = bcd_to_bin(regs[5] & 0x1F) as u32;
+        let year = 2000 + bcd_to_bin(regs[6]) as i32;
+
+        Ok(days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second)
+    }
+
+    /// Set the RTC from Unix seconds (UTC).
+    pub fn write_unix(&self, unix: i64) -> anyhow::Result<()> {
+        let days = unix.div_euclid(86_400);
+        let secs = unix.rem_euclid(86_400);
+        let (year, month, day) = civil_from_days(days);
+        anyhow::ensure!((2000..2100).contains(&year), "year {} out of DS3231 range", year);
+
+        let weekday = ((days + 4).rem_euclid(7) + 1) as u8; // 1970-01-01 was a Thursday
+        let frame = [
+            REG_SECONDS,
+            bin_to_bcd((secs % 60) as u8),
+            bin_to_bcd((secs / 60 % 60) as u8),
+            bin_to_bcd((secs / 3600) as u8),
+            weekday,
+            bin_to_bcd(day as u8),
+            bin_to_bcd(month as u8),
+            bin_to_bcd((year - 2000) as u8),
+        ];
+        let mut bus = self.bus.lock().unwrap();
+        bus.write(I2C_ADDR_DS3231, &frame, I2C_TIMEOUT_TICKS_RTC)?;
+        Ok(())
+    }
+}
+
+fn bcd_to_bin(v: u8) -> u8 {
+    (v >> 4) * 10 + (v & 0x0F)
+}
+
+fn bin_to_bcd(v: u8) -> u8 {
+    ((v / 10) << 4) | (v % 10)
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date (H. Hinnant).
+fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year } as i64;
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let m = month as i64;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Inverse of [`days_from_civil`]: `(year, month, day)`.
+pub fn civil_from_days(days: i64) -> (i32, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = (yoe + era * 400 + if month <= 2 { 1 } else { 0 }) as i32;
+    (year, month, day)
+}
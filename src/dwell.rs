@@ -0,0 +1,86 @@
+// PlastiWatch V2 — Activity Dwell Time
+//
+// How long the wearer has spent in each `ActivityClass`.  The AI task calls
+// `switch` whenever it reports an activity; the time since the previous call
+// is credited to the previous activity.  `totals` adds the still-running
+// stretch, so a reader sees up-to-date figures without waiting for the next
+// change.  The tally lives in `SystemState` and is shown on the summary
+// screen and by the serial `dwell` command.
+//
+// Times are `now_ms()` differences taken with `ticks::elapsed_ms`, so the
+// ~49-day wrap of `now_ms()` costs nothing as long as one stretch is shorter
+// than that; totals saturate rather than wrap.  The tally starts empty at boot
+// and is cleared at local midnight (`DWELL_RESET_AT_MIDNIGHT`, once the clock
+// is set), by a double-click on the summary screen or by `dwell reset`.
+
+use crate::config::*;
+use crate::events::ActivityClass;
+use crate::ticks;
+
+#[derive(Debug, Clone, Copy)]
+pub struct DwellTally {
+    /// Completed time per class (ms), in `ActivityClass::index` order.
+    totals_ms: [u32; EI_LABEL_COUNT + 1],
+    /// Activity being timed and `now_ms()` when it started.
+    current: ActivityClass,
+    since_ms: u32,
+    /// Local day number the tally belongs to, once the clock is set.
+    day: Option<i64>,
+}
+
+impl DwellTally {
+    pub fn new(activity: ActivityClass, now_ms: u32) -> Self {
+        Self {
+            totals_ms: [0; EI_LABEL_COUNT + 1],
+            current: activity,
+            since_ms: now_ms,
+            day: None,
+        }
+    }
+
+    /// Reset if `today` (local day number, `None` while the clock is unset)
+    /// differs from the tally's day.
+    pub fn roll_over(&mut self, today: Option<i64>, now_ms: u32) {
+        let Some(today) = today else { return };
+        if self.day.is_some_and(|day| day != today) {
+            log::info!("New day — activity tally reset");
+            self.reset(now_ms);
+        }
+        self.day = Some(today);
+    }
+
+    /// Credit the time since the last call to the previous activity and
+    /// start timing `activity`.
+    pub fn switch(&mut self, activity: ActivityClass, now_ms: u32) {
+        let total = &mut self.totals_ms[self.current.index()];
+        *total = total.saturating_add(ticks::elapsed_ms(now_ms, self.since_ms));
+        self.current = activity;
+        self.since_ms = now_ms;
+    }
+
+    /// Time per class (ms) including the running stretch.
+    pub fn totals(&self, now_ms: u32) -> [u32; EI_LABEL_COUNT + 1] {
+        let mut totals = self.totals_ms;
+        let running = &mut totals[self.current.index()];
+        *running = running.saturating_add(ticks::elapsed_ms(now_ms, self.since_ms));
+        totals
+    }
+
+    /// Forget everything so far; keep timing the current activity from now.
+    pub fn reset(&mut self, now_ms: u32) {
+        *self = Self {
+            day: self.day,
+            ..Self::new(self.current, now_ms)
+        };
+    }
+}
+
+/// Compact duration for the summary screen: "3h05", "42m" or "<1m".
+pub fn format_duration(ms: u32) -> String {
+    let minutes = ms / 60_000;
+    match minutes {
+        0 => "<1m".into(),
+        1..=59 => format!("{}m", minutes),
+        _ => format!("{}h{:02}", minutes / 60, minutes % 60),
+    }
+}
@@ -8,13 +8,46 @@
 //   2. FFI mode — uncomment the `edge-impulse` feature in Cargo.toml and
 //      enable the build.rs EI compilation to link the real classifier.
 //
-// The AI task calls `classify(features)` with a 375-float buffer
-// (125 samples × 3 axes) and receives back the winning label index and its
-// confidence.
+// The AI task calls `classify(features)` with a window of frames — by default
+// 375 floats (125 samples × 3 axes, or 750 with `EI_USE_GYRO`) — and receives
+// back the winning label index and its confidence.  The window is a slice, so
+// its length can be changed for experiments (`EI_WINDOW_SAMPLES`);
+// `check_window` says whether the linked back-end can take a given length:
+// the stub takes any whole number of frames, the real model only its own
+// `EI_DSP_INPUT_FRAME_SIZE`.  A window that fails the check is not
+// classified.  `classify_full` returns every class with its confidence
+// instead, for logging near-misses or drawing a probability histogram.
+//
+// When the impulse has an anomaly-detection block (`EI_HAS_ANOMALY`), the
+// anomaly score is carried on every `ClassifierResult`; a score above
+// `EI_ANOMALY_THRESHOLD` means the motion doesn't look like anything the model
+// was trained on, and no activity is reported for that window.
+//
+// The firmware's class order is `LABELS`.  The real back-end maps the
+// model's outputs onto it by label name, not position, so a retrained model
+// that lists its labels in another order still scores the right classes; a
+// label the firmware doesn't know is logged and ignored.
+//
+// `classify_full` also reports how long the inference took (DSP, classifier,
+// anomaly block and the whole call); the AI task uses it to watch the margin
+// against the window hop.  The stub reports zeros.
+//
+// The decision itself — thresholds, anomaly gate, NaN handling — is the pure
+// `decision::decide`; `select` feeds it the current thresholds.
+//
+// For field tuning, `set_threshold` (the serial `threshold` command) replaces
+// every class's confidence threshold until the next boot.
+
+use std::sync::atomic::{AtomicU32, Ordering};
 
 use crate::config::*;
+use crate::decision;
 use crate::events::ActivityClass;
 
+/// Runtime threshold for every class as `f32` bits; 0 = use
+/// `EI_CONFIDENCE_THRESHOLDS`.
+static THRESHOLD_OVERRIDE: AtomicU32 = AtomicU32::new(0);
+
 // ---------------------------------------------------------------------------
 // Public interface
 // ---------------------------------------------------------------------------
@@ -24,35 +57,167 @@ use crate::events::ActivityClass;
 pub struct ClassifierResult {
     pub activity: ActivityClass,
     pub confidence: f32,
+    /// Anomaly score for the whole window, or `None` when the impulse has
+    /// no anomaly block (and always with the stub back-end).
+    pub anomaly: Option<f32>,
+}
+
+/// Time spent on one inference, in microseconds.  All zero with the stub
+/// back-end.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InferenceTiming {
+    pub dsp_us: u32,
+    pub classification_us: u32,
+    pub anomaly_us: u32,
+    /// The whole back-end call, including the stages above.
+    pub total_us: u32,
+}
+
+/// Every class's score for one window plus what computing them cost.
+#[derive(Debug, Clone, Copy)]
+pub struct Inference {
+    pub scores: [ClassifierResult; EI_LABEL_COUNT],
+    pub timing: InferenceTiming,
 }
 
-/// Labels matching the Edge Impulse model output order.
+/// Raw output of one inference back-end call.
+struct InferenceOutput {
+    /// Per-class confidence scores [idle, snake, updown, wave].
+    scores: [f32; EI_LABEL_COUNT],
+    anomaly: Option<f32>,
+    timing: InferenceTiming,
+}
+
+/// Firmware class order (`ActivityClass::index`).  The FFI back-end matches
+/// model outputs to these by name; the stub produces them in this order.
 pub const LABELS: [&str; EI_LABEL_COUNT] = ["idle", "snake", "updown", "wave"];
 
+/// Confidence class `index` (in `LABELS` order) must reach to be reported.
+pub fn threshold(index: usize) -> f32 {
+    match THRESHOLD_OVERRIDE.load(Ordering::Relaxed) {
+        0 => EI_CONFIDENCE_THRESHOLDS[index],
+        bits => f32::from_bits(bits),
+    }
+}
+
+/// Every class's current threshold, in `LABELS` order.
+pub fn thresholds() -> decision::Scores {
+    core::array::from_fn(threshold)
+}
+
+/// Use `value` as every class's threshold until reboot.
+pub fn set_threshold(value: f32) -> anyhow::Result<()> {
+    if !(value > 0.0 && value <= 1.0) {
+        anyhow::bail!("threshold {} outside (0, 1]", value);
+    }
+    THRESHOLD_OVERRIDE.store(value.to_bits(), Ordering::Relaxed);
+    log::info!("Confidence threshold set to {:.2} for all classes", value);
+    Ok(())
+}
+
+/// Whether the inference back-end accepts a window of `len` floats.
+pub fn check_window(len: usize) -> anyhow::Result<()> {
+    if len == 0 || len % EI_RAW_SAMPLES_PER_FRAME != 0 {
+        anyhow::bail!(
+            "window of {} floats is not a whole number of {}-value frames",
+            len,
+            EI_RAW_SAMPLES_PER_FRAME
+        );
+    }
+    if cfg!(feature = "edge-impulse") && len != EI_DSP_INPUT_FRAME_SIZE {
+        anyhow::bail!(
+            "model expects {} floats per window, got {}",
+            EI_DSP_INPUT_FRAME_SIZE,
+            len
+        );
+    }
+    Ok(())
+}
+
 /// Run activity classification on a filled feature buffer.
 ///
-/// `features` must contain exactly `EI_DSP_INPUT_FRAME_SIZE` (375) floats
-/// representing 125 consecutive 3-axis accelerometer readings.
+/// `features` holds consecutive frames of `EI_RAW_SAMPLES_PER_FRAME` values
+/// (accX, accY, accZ and, with `EI_USE_GYRO`, gyrX, gyrY, gyrZ), as many as
+/// [`check_window`] allows — `EI_DSP_INPUT_FRAME_SIZE` floats for the real
+/// model.
 ///
-/// Returns `Some(result)` when inference succeeds and confidence exceeds the
-/// threshold, or `None` when the best prediction is below threshold or an
-/// error occurred.
-pub fn classify(features: &[f32; EI_DSP_INPUT_FRAME_SIZE]) -> Option<ClassifierResult> {
-    let predictions = run_inference(features)?;
-
-    // Find the label with highest confidence
-    let (best_idx, &best_val) = predictions
-        .iter()
-        .enumerate()
-        .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())?;
-
-    if best_val >= EI_CONFIDENCE_THRESHOLD {
-        Some(ClassifierResult {
-            activity: ActivityClass::from_label(LABELS[best_idx]),
-            confidence: best_val,
-        })
-    } else {
-        None
+/// Each class is compared against its own threshold ([`threshold`]);
+/// the highest-scoring class that clears its threshold wins.
+///
+/// Returns `Some(result)` when inference succeeds and some class clears its
+/// threshold, or `None` when every prediction is below threshold, the window
+/// is anomalous, or an error occurred (including a window of the wrong
+/// length).
+pub fn classify(features: &[f32]) -> Option<ClassifierResult> {
+    select(&classify_full(features)?.scores)
+}
+
+/// Run activity classification and return every class with its confidence,
+/// in label order (`LABELS`), along with the inference timing.  No thresholds
+/// are applied.
+///
+/// Returns `None` when inference fails, the window fails [`check_window`] or
+/// it contains a NaN or infinite value (which the model would only turn into
+/// garbage scores).
+pub fn classify_full(features: &[f32]) -> Option<Inference> {
+    if let Err(e) = check_window(features.len()) {
+        log::warn!("{} — skipping window", e);
+        return None;
+    }
+    if let Some(i) = features.iter().position(|v| !v.is_finite()) {
+        log::warn!("Non-finite feature {} at index {} — skipping window", features[i], i);
+        return None;
+    }
+    let output = run_inference(features)?;
+
+    Some(Inference {
+        scores: core::array::from_fn(|i| ClassifierResult {
+            activity: ActivityClass::from_label(LABELS[i]),
+            confidence: output.scores[i],
+            anomaly: output.anomaly,
+        }),
+        timing: output.timing,
+    })
+}
+
+/// Pick the winner from a `classify_full` result: the highest-confidence
+/// class among those above their own [`threshold`].
+/// An anomalous window (score above `EI_ANOMALY_THRESHOLD`) selects nothing.
+pub fn select(scores: &[ClassifierResult; EI_LABEL_COUNT]) -> Option<ClassifierResult> {
+    let anomaly = scores[0].anomaly;
+    if let Some(a) = anomaly.filter(|&a| a > EI_ANOMALY_THRESHOLD) {
+        log::info!("Unknown activity (anomaly {:.2})", a);
+    }
+
+    let confidences = scores.map(|r| r.confidence);
+    decision::decide(&confidences, &thresholds(), anomaly, EI_ANOMALY_THRESHOLD)
+        .map(|d| scores[d.index])
+}
+
+/// Order results by confidence, treating NaN as the lowest possible score so
+/// a degenerate model output can never win (or panic a sort).
+pub fn compare_confidence(a: &ClassifierResult, b: &ClassifierResult) -> core::cmp::Ordering {
+    decision::compare_scores(a.confidence, b.confidence)
+}
+
+/// Standardise each axis of `features` in place: subtract the per-axis mean
+/// and divide by the per-axis standard deviation over the window.
+///
+/// The deviation is clamped to `EI_NORMALIZE_MIN_STD` so a window recorded
+/// with the device perfectly still doesn't divide by zero.
+pub fn normalize(features: &mut [f32]) {
+    let n = (features.len() / EI_RAW_SAMPLES_PER_FRAME) as f32;
+
+    for axis in 0..EI_RAW_SAMPLES_PER_FRAME {
+        let values = || features.iter().skip(axis).step_by(EI_RAW_SAMPLES_PER_FRAME);
+
+        let mean = values().sum::<f32>() / n;
+        let var = values().map(|v| (v - mean) * (v - mean)).sum::<f32>() / n;
+        let std = var.sqrt().max(EI_NORMALIZE_MIN_STD);
+
+        for v in features.iter_mut().skip(axis).step_by(EI_RAW_SAMPLES_PER_FRAME) {
+            *v = (*v - mean) / std;
+        }
     }
 }
 
@@ -60,8 +225,9 @@ pub fn classify(features: &[f32; EI_DSP_INPUT_FRAME_SIZE]) -> Option<ClassifierR
 // Inference back-end (swap between stub / real FFI)
 // ---------------------------------------------------------------------------
 
-/// Returns per-class confidence scores [idle, snake, updown, wave].
-fn run_inference(features: &[f32; EI_DSP_INPUT_FRAME_SIZE]) -> Option<[f32; EI_LABEL_COUNT]> {
+/// Returns per-class confidence scores [idle, snake, updown, wave] and the
+/// anomaly score, if any.
+fn run_inference(features: &[f32]) -> Option<InferenceOutput> {
     #[cfg(not(feature = "edge-impulse"))]
     {
         return stub_inference(features);
@@ -77,10 +243,16 @@ fn run_inference(features: &[f32; EI_DSP_INPUT_FRAME_SIZE]) -> Option<[f32; EI_L
 // Stub back-end — development / testing without the C++ SDK
 // ---------------------------------------------------------------------------
 #[cfg(not(feature = "edge-impulse"))]
-fn stub_inference(_features: &[f32; EI_DSP_INPUT_FRAME_SIZE]) -> Option<[f32; EI_LABEL_COUNT]> {
+fn stub_inference(_features: &[f32]) -> Option<InferenceOutput> {
     // Simple heuristic: use mean absolute acceleration to guess activity.
     // This lets the UI pipeline work end-to-end before the real model is linked.
-    let mean_abs: f32 = _features.iter().map(|v| v.abs()).sum::<f32>() / _features.len() as f32;
+    // Only the accelerometer axes of each frame are used, so the thresholds
+    // hold in both 3-axis and 6-axis mode.
+    let frames = _features.len() / EI_RAW_SAMPLES_PER_FRAME;
+    let accel = _features
+        .chunks_exact(EI_RAW_SAMPLES_PER_FRAME)
+        .flat_map(|frame| &frame[..3]);
+    let mean_abs: f32 = accel.map(|v| v.abs()).sum::<f32>() / (frames * 3) as f32;
 
     let preds = if mean_abs < 0.3 {
         [0.90, 0.03, 0.04, 0.03] // idle
@@ -97,7 +269,11 @@ fn stub_inference(_features: &[f32; EI_DSP_INPUT_FRAME_SIZE]) -> Option<[f32; EI
         mean_abs,
         preds
     );
-    Some(preds)
+    Some(InferenceOutput {
+        scores: preds,
+        anomaly: None,
+        timing: InferenceTiming::default(),
+    })
 }
 
 // ---------------------------------------------------------------------------
@@ -119,11 +295,25 @@ mod ffi {
         pub value: f32,
     }
 
-    // The full struct has more fields; we only access `classification`.
+    /// `ei_impulse_result_timing_t`: per-stage times in ms, then in µs.
+    #[repr(C)]
+    pub struct EiTiming {
+        pub sampling: i32,
+        pub dsp: i32,
+        pub classification: i32,
+        pub anomaly: i32,
+        pub dsp_us: i64,
+        pub classification_us: i64,
+        pub anomaly_us: i64,
+    }
+
+    // The full struct has more fields; we only access `classification`,
+    // `anomaly` and `timing`.
     #[repr(C)]
     pub struct EiImpulseResult {
         pub classification: [EiClassification; super::EI_LABEL_COUNT],
         pub anomaly: f32,
+        pub timing: EiTiming,
     }
 
     extern "C" {
@@ -135,8 +325,19 @@ mod ffi {
     }
 }
 
+/// Log a model label with no `ActivityClass` once per boot (not every window).
+#[cfg(feature = "edge-impulse")]
+fn warn_unknown_label(label: &str) {
+    use std::sync::atomic::AtomicBool;
+
+    static WARNED: AtomicBool = AtomicBool::new(false);
+    if !WARNED.swap(true, Ordering::Relaxed) {
+        log::warn!("Model label \"{}\" matches no activity class — ignored", label);
+    }
+}
+
 #[cfg(feature = "edge-impulse")]
-fn ffi_inference(features: &[f32; EI_DSP_INPUT_FRAME_SIZE]) -> Option<[f32; EI_LABEL_COUNT]> {
+fn ffi_inference(features: &[f32]) -> Option<InferenceOutput> {
     use std::ffi::CStr;
 
     // Signal callback reads directly from the features slice.
@@ -165,20 +366,47 @@ fn ffi_inference(features: &[f32; EI_DSP_INPUT_FRAME_SIZE]) -> Option<[f32; EI_L
 
         let mut result: ffi::EiImpulseResult = core::mem::zeroed();
 
+        let started = std::time::Instant::now();
         let err = ffi::run_classifier(&mut signal, &mut result, false);
+        let total_us = started.elapsed().as_micros() as u32;
         if err != 0 {
             log::error!("Edge Impulse classifier error: {}", err);
             return None;
         }
 
+        // Map by label name: the model's output order needn't match `LABELS`.
         let mut preds = [0.0f32; EI_LABEL_COUNT];
-        for i in 0..EI_LABEL_COUNT {
-            preds[i] = result.classification[i].value;
-            let label = CStr::from_ptr(result.classification[i].label);
-            log::debug!("{}: {:.4}", label.to_str().unwrap_or("?"), preds[i]);
+        for c in &result.classification {
+            if c.label.is_null() {
+                continue;
+            }
+            let label = CStr::from_ptr(c.label).to_str().unwrap_or("?");
+            log::debug!("{}: {:.4}", label, c.value);
+            match ActivityClass::from_label(label) {
+                ActivityClass::Unknown => warn_unknown_label(label),
+                activity => preds[activity.index()] = c.value,
+            }
+        }
+
+        // `anomaly` is only meaningful when the impulse has an anomaly block.
+        let anomaly = EI_HAS_ANOMALY.then_some(result.anomaly);
+        if let Some(a) = anomaly {
+            log::debug!("anomaly: {:.4}", a);
         }
 
+        let us = |t: i64| t.clamp(0, u32::MAX as i64) as u32;
+        let timing = InferenceTiming {
+            dsp_us: us(result.timing.dsp_us),
+            classification_us: us(result.timing.classification_us),
+            anomaly_us: us(result.timing.anomaly_us),
+            total_us,
+        };
+
         SIGNAL_BUF = std::ptr::null();
-        Some(preds)
+        Some(InferenceOutput {
+            scores: preds,
+            anomaly,
+            timing,
+        })
     }
 }
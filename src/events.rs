@@ -1,7 +1,10 @@
 // PlastiWatch V2 — System Events & Data Types
 
+use crate::config::ACTIVITY_DISPLAY_NAMES;
+use crate::drivers::imu::CalibrationOffsets;
+
 // ---------------------------------------------------------------------------
-// Sensor Data (6-axis IMU reading from MPU6050)
+// Sensor Data (6-axis IMU reading + die temperature from MPU6050)
 // ---------------------------------------------------------------------------
 #[derive(Debug, Clone, Copy, Default)]
 pub struct SensorData {
@@ -11,6 +14,10 @@ pub struct SensorData {
     pub gx: f32,
     pub gy: f32,
     pub gz: f32,
+    /// MPU6050 die temperature (°C).
+    pub temp_c: f32,
+    /// `now_ms()` when the sample was read from the IMU.
+    pub timestamp_ms: u32,
 }
 
 // ---------------------------------------------------------------------------
@@ -22,17 +29,15 @@ pub enum ActivityClass {
     Snake,
     UpDown,
     Wave,
+    /// No class has cleared its threshold for a while.  Not a model output.
+    Unknown,
 }
 
 impl ActivityClass {
-    /// Human-readable label shown on the OLED activity screen.
+    /// Human-readable label shown on the OLED activity screen, from
+    /// `ACTIVITY_DISPLAY_NAMES`.
     pub fn display_name(&self) -> &'static str {
-        match self {
-            Self::Idle   => "normal",
-            Self::Snake  => "fall!",
-            Self::UpDown => "walking",
-            Self::Wave   => "running",
-        }
+        ACTIVITY_DISPLAY_NAMES[self.index()]
     }
 
     /// Map an Edge Impulse label string to an `ActivityClass`.
@@ -42,14 +47,37 @@ impl ActivityClass {
             "snake"  => Self::Snake,
             "updown" => Self::UpDown,
             "wave"   => Self::Wave,
-            _        => Self::Idle,
+            _        => Self::Unknown,
+        }
+    }
+
+    /// Position of this class in the model's label order (`ei::LABELS`).
+    /// `Unknown` sits one past the model's classes.
+    pub fn index(&self) -> usize {
+        match self {
+            Self::Idle    => 0,
+            Self::Snake   => 1,
+            Self::UpDown  => 2,
+            Self::Wave    => 3,
+            Self::Unknown => 4,
+        }
+    }
+
+    /// Inverse of [`Self::index`].
+    pub fn from_index(index: usize) -> Self {
+        match index {
+            0 => Self::Idle,
+            1 => Self::Snake,
+            2 => Self::UpDown,
+            3 => Self::Wave,
+            _ => Self::Unknown,
         }
     }
 }
 
 impl Default for ActivityClass {
     fn default() -> Self {
-        Self::Idle
+        Self::Unknown
     }
 }
 
@@ -58,14 +86,61 @@ impl Default for ActivityClass {
 // ---------------------------------------------------------------------------
 #[derive(Debug, Clone, Copy)]
 pub enum UiEvent {
-    /// AI classified a new activity.
-    UpdateActivity(ActivityClass),
-    /// Battery level changed (0.0–100.0 %).
-    UpdateBattery(f32),
+    /// AI classified a new activity, with the latest window's confidence
+    /// in it (0.0–1.0).
+    UpdateActivity(ActivityClass, f32),
+    /// AI classified a fall ("snake"); the UI holds an alert until acknowledged.
+    FallDetected,
+    /// New battery reading: level (0.0–100.0 %) and the smoothed voltage it
+    /// was derived from (V).
+    UpdateBattery { pct: f32, volts: f32 },
     /// Single button click detected.
     ButtonSingleClick,
     /// Double button click detected.
     ButtonDoubleClick,
+    /// Triple button click detected.
+    ButtonTripleClick,
     /// Long button press (≥ 3 s) detected.
     ButtonLongPress,
+    /// Button still held toward a long press (0.0–1.0 of the long-press time).
+    ButtonHoldProgress(f32),
+    /// Button released before the long press completed.
+    ButtonHoldCancelled,
+    /// Button has read pressed for `BUTTON_STUCK_MS`: treated as a hardware
+    /// fault, no press events are sent until it is released.
+    ButtonStuck,
+    /// The stuck button was released; normal handling resumes.
+    ButtonRecovered,
+    /// Auto-repeat tick while the button is held (when enabled in `InputConfig`).
+    ButtonRepeat,
+    /// Wrist raised toward the face and held there (raise-to-wake gesture).
+    WristRaised,
+    /// The watch is now worn the other way up: `true` = render rotated 180°.
+    OrientationChanged(bool),
+    /// Step count since boot changed.
+    UpdateSteps(u32),
+    /// No IMU sample has reached the AI task for `SENSOR_LOST_TIMEOUT_MS`, or
+    /// the IMU hasn't answered the sensor task for
+    /// `SENSOR_DISCONNECTED_AFTER_MS`.
+    SensorLost,
+    /// Samples are arriving again after `SensorLost` (the IMU is back).
+    SensorRestored,
+    /// The sensor task finished a requested calibration: the offsets now in
+    /// use, or `None` if it was rejected (device moving) or failed.
+    CalibrationFinished(Option<CalibrationOffsets>),
+    /// The AI task's feature window is filling (0.0–1.0 of a full window);
+    /// 0.0 = not collecting.  Sent every `EI_PROGRESS_EVERY_SAMPLES`.
+    WindowProgress(f32),
+    /// External power detected (battery voltage above the charge threshold
+    /// or steadily rising).
+    ChargingStarted,
+    /// External power removed.
+    ChargingStopped,
+    /// Change the user's display brightness (SSD1306 contrast, 0–255).
+    SetBrightness(u8),
+    /// Switch inverted (dark-on-light) rendering on or off.
+    SetInvert(bool),
+    /// Start or stop demo mode (the activity screen cycling through every
+    /// activity instead of following the classifier).
+    SetDemo(bool),
 }
@@ -0,0 +1,141 @@
+// PlastiWatch V2 — IMU Sample Filter
+//
+// The MPU6050's DLPF leaves some high-frequency noise, and a single spiky
+// sample can push a 2-second window toward "snake" or "wave" (the stub
+// back-end's mean-|a| heuristic is especially sensitive).  `ImuFilter`
+// smooths each of the six axes independently in the sensor task, before a
+// sample reaches the AI task, the shared state or a training capture:
+//
+//   Off       samples pass through untouched
+//   LowPass   first-order IIR low-pass with the given cutoff; the smoothing
+//             factor follows the actual sample interval, so it holds at the
+//             idle rate too
+//   Median    moving median of the last `SENSOR_MEDIAN_WINDOW` samples,
+//             which removes isolated spikes without softening real edges
+//
+// The choice is `SENSOR_FILTER` in config.rs.  The model sees what the filter
+// outputs, so data for training must be captured with the same setting it
+// will run with.  `reset` forgets the history; the sensor task calls it after
+// the IMU is re-initialised or recalibrated and when the sample rate changes,
+// so stale readings never bleed into new ones.
+//
+// `GravityFilter` is a separate high-pass for the AI task's features only
+// (`EI_REMOVE_GRAVITY`): it tracks a running mean of each accelerometer axis —
+// mostly gravity, which shifts with wrist angle — and subtracts it, leaving
+// the dynamic part of the motion.  The wrist-raise, orientation and step
+// detectors still get the raw samples, which they need gravity in.
+
+use crate::config::*;
+use crate::events::SensorData;
+
+/// Accelerometer X/Y/Z, then gyro X/Y/Z.
+const AXES: usize = 6;
+
+/// How IMU samples are smoothed before use.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SampleFilter {
+    Off,
+    /// First-order low-pass at `cutoff_hz`.
+    LowPass { cutoff_hz: f32 },
+    /// Moving median over `SENSOR_MEDIAN_WINDOW` samples.
+    Median,
+}
+
+pub struct ImuFilter {
+    kind: SampleFilter,
+    /// Low-pass output per axis, `None` until the first sample.
+    smoothed: Option<[f32; AXES]>,
+    /// Median ring per axis and how many slots are filled.
+    history: [[f32; SENSOR_MEDIAN_WINDOW]; AXES],
+    len: usize,
+    next: usize,
+}
+
+impl ImuFilter {
+    pub fn new(kind: SampleFilter) -> Self {
+        Self {
+            kind,
+            smoothed: None,
+            history: [[0.0; SENSOR_MEDIAN_WINDOW]; AXES],
+            len: 0,
+            next: 0,
+        }
+    }
+
+    /// Forget all previous samples; the next one passes through as is.
+    pub fn reset(&mut self) {
+        *self = Self::new(self.kind);
+    }
+
+    /// Filter `data` in place.  `interval_ms` is the time since the previous
+    /// sample (the current sample interval).
+    pub fn apply(&mut self, data: &mut SensorData, interval_ms: u64) {
+        let mut axes = [
+            &mut data.ax,
+            &mut data.ay,
+            &mut data.az,
+            &mut data.gx,
+            &mut data.gy,
+            &mut data.gz,
+        ];
+
+        match self.kind {
+            SampleFilter::Off => {}
+            SampleFilter::LowPass { cutoff_hz } => {
+                // alpha = dt / (RC + dt), RC = 1 / (2π fc)
+                let dt = interval_ms as f32 / 1000.0;
+                let rc = 1.0 / (2.0 * core::f32::consts::PI * cutoff_hz);
+                let alpha = dt / (rc + dt);
+                let smoothed = self.smoothed.get_or_insert(core::array::from_fn(|i| *axes[i]));
+                for (value, out) in axes.iter_mut().zip(smoothed.iter_mut()) {
+                    *out += alpha * (**value - *out);
+                    **value = *out;
+                }
+            }
+            SampleFilter::Median => {
+                for (value, ring) in axes.iter_mut().zip(self.history.iter_mut()) {
+                    ring[self.next] = **value;
+                }
+                self.next = (self.next + 1) % SENSOR_MEDIAN_WINDOW;
+                self.len = (self.len + 1).min(SENSOR_MEDIAN_WINDOW);
+                for (value, ring) in axes.iter_mut().zip(self.history.iter()) {
+                    let mut window = *ring;
+                    let window = &mut window[..self.len];
+                    window.sort_unstable_by(f32::total_cmp);
+                    **value = window[self.len / 2];
+                }
+            }
+        }
+    }
+}
+
+/// Running per-axis mean of the accelerometer (≈ gravity), subtracted from
+/// each sample to leave the dynamic acceleration.
+pub struct GravityFilter {
+    /// Gravity estimate per axis, `None` until the first sample.
+    gravity: Option<[f32; 3]>,
+}
+
+impl GravityFilter {
+    pub fn new() -> Self {
+        Self { gravity: None }
+    }
+
+    /// Forget the estimate; the next sample seeds it (and comes out as 0).
+    pub fn reset(&mut self) {
+        self.gravity = None;
+    }
+
+    /// `accel` minus the running mean, updated with time constant
+    /// `EI_GRAVITY_TAU_MS`.  `interval_ms` is the time since the previous
+    /// sample.
+    pub fn apply(&mut self, accel: [f32; 3], interval_ms: u64) -> [f32; 3] {
+        let dt = interval_ms as f32;
+        let alpha = dt / (EI_GRAVITY_TAU_MS as f32 + dt);
+        let gravity = self.gravity.get_or_insert(accel);
+        for (g, a) in gravity.iter_mut().zip(accel) {
+            *g += alpha * (a - *g);
+        }
+        core::array::from_fn(|i| accel[i] - gravity[i])
+    }
+}
@@ -0,0 +1,155 @@
+// PlastiWatch V2 — Activity History
+//
+// A bounded log of classification changes — when, which activity, how
+// confident — for reviewing a day's activity without any radio.  The AI task
+// calls `record` whenever the reported activity changes; the log keeps the
+// last `HISTORY_LEN` entries in RAM, overwriting the oldest.
+//
+// The ring is mirrored to NVS (see `nvs::Store`) as one blob: the power task
+// writes it every `HISTORY_FLUSH_INTERVAL_MS` and before deep sleep, and only
+// when something was recorded since the last write, so flash wear stays at
+// a handful of writes an hour at most.  NVS does its own wear levelling and
+// the blob size is fixed, so the log wraps rather than filling the partition.
+//
+// Entries carry the wall-clock time when the clock has been set and the
+// uptime in any case; dump them with the serial `history` command.
+
+use std::sync::Mutex;
+
+use crate::clock;
+use crate::config::*;
+use crate::events::ActivityClass;
+use crate::ticks;
+
+/// Bump when the blob layout changes; older blobs are then ignored.
+const FORMAT_VERSION: u8 = 1;
+const ENTRY_LEN: usize = 10;
+/// Version, entry count, then the entries oldest first.
+pub const BLOB_MAX: usize = 2 + HISTORY_LEN * ENTRY_LEN;
+
+static LOG: Mutex<History> = Mutex::new(History::new());
+
+/// One logged activity change.
+#[derive(Debug, Clone, Copy)]
+pub struct Entry {
+    /// Unix time (UTC seconds), or 0 if the clock wasn't set.
+    pub unix: u32,
+    /// Seconds since the boot that recorded it.
+    pub uptime_s: u32,
+    pub activity: ActivityClass,
+    /// Confidence in percent.
+    pub confidence: u8,
+}
+
+impl Entry {
+    const EMPTY: Self = Self {
+        unix: 0,
+        uptime_s: 0,
+        activity: ActivityClass::Unknown,
+        confidence: 0,
+    };
+}
+
+struct History {
+    entries: [Entry; HISTORY_LEN],
+    len: usize,
+    /// Slot the next entry goes into.
+    next: usize,
+    /// Bumped on every `record`; compared with `flushed` to skip writes.
+    recorded: u32,
+    flushed: u32,
+}
+
+impl History {
+    const fn new() -> Self {
+        Self {
+            entries: [Entry::EMPTY; HISTORY_LEN],
+            len: 0,
+            next: 0,
+            recorded: 0,
+            flushed: 0,
+        }
+    }
+
+    fn push(&mut self, entry: Entry) {
+        self.entries[self.next] = entry;
+        self.next = (self.next + 1) % HISTORY_LEN;
+        self.len = (self.len + 1).min(HISTORY_LEN);
+    }
+
+    /// Entries oldest first.
+    fn iter(&self) -> impl Iterator<Item = &Entry> {
+        let start = (self.next + HISTORY_LEN - self.len) % HISTORY_LEN;
+        (0..self.len).map(move |i| &self.entries[(start + i) % HISTORY_LEN])
+    }
+}
+
+/// Log a change to `activity`.  Repeats of the most recent activity are
+/// ignored, so the caller can report every decision.
+pub fn record(activity: ActivityClass, confidence: f32) {
+    let mut log = LOG.lock().unwrap();
+    if log.iter().last().is_some_and(|e| e.activity == activity) {
+        return;
+    }
+    log.push(Entry {
+        unix: clock::unix_now().map_or(0, |t| t as u32),
+        uptime_s: ticks::uptime_s(),
+        activity,
+        confidence: (confidence.clamp(0.0, 1.0) * 100.0).round() as u8,
+    });
+    log.recorded = log.recorded.wrapping_add(1);
+}
+
+/// Copy of the log, oldest first.
+pub fn entries() -> Vec<Entry> {
+    LOG.lock().unwrap().iter().copied().collect()
+}
+
+/// Serialised log plus a token for [`mark_flushed`], or `None` if nothing
+/// was recorded since the last successful write.
+pub fn unflushed_blob() -> Option<(Vec<u8>, u32)> {
+    let log = LOG.lock().unwrap();
+    if log.recorded == log.flushed {
+        return None;
+    }
+
+    let mut blob = Vec::with_capacity(2 + log.len * ENTRY_LEN);
+    blob.push(FORMAT_VERSION);
+    blob.push(log.len as u8);
+    for e in log.iter() {
+        blob.extend_from_slice(&e.unix.to_le_bytes());
+        blob.extend_from_slice(&e.uptime_s.to_le_bytes());
+        blob.push(e.activity.index() as u8);
+        blob.push(e.confidence);
+    }
+    Some((blob, log.recorded))
+}
+
+/// The blob from [`unflushed_blob`] reached flash.
+pub fn mark_flushed(token: u32) {
+    LOG.lock().unwrap().flushed = token;
+}
+
+/// Replace the log with a blob read from flash.  Returns `false`, leaving the
+/// log untouched, if the blob is malformed or from another format version.
+pub fn restore(blob: &[u8]) -> bool {
+    if blob.len() < 2 || blob[0] != FORMAT_VERSION {
+        return false;
+    }
+    let count = blob[1] as usize;
+    if count > HISTORY_LEN || blob.len() != 2 + count * ENTRY_LEN {
+        return false;
+    }
+
+    let mut log = LOG.lock().unwrap();
+    *log = History::new();
+    for chunk in blob[2..].chunks_exact(ENTRY_LEN) {
+        log.push(Entry {
+            unix: u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]),
+            uptime_s: u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]),
+            activity: ActivityClass::from_index(chunk[8] as usize),
+            confidence: chunk[9],
+        });
+    }
+    true
+}
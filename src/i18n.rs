@@ -0,0 +1,216 @@
+// PlastiWatch V2 — UI Strings
+//
+// The handful of words the wearer sees (activity names, boot check, prompts),
+// per `Language`.  `UI_LANGUAGE` in config.rs picks one at compile time;
+// `strings()` returns its table.  Brand names ("PlastiWatch", "PlastiBytes")
+// and the hidden stats / diagnostics screens stay as they are.
+//
+// The OLED fonts are ISO 8859-1 (Latin-1), which covers French, German and
+// Spanish accents.  A table may only use characters from that set — checked
+// at compile time below — so a language needing anything else has to spell
+// its strings in plain ASCII.  The zeroing screen's lines are also checked
+// against its width, so each stays on one line.
+
+use crate::config::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    English,
+    French,
+    German,
+    Spanish,
+}
+
+/// Everything the UI shows in words.
+pub struct Strings {
+    /// Activity labels in `ActivityClass::index` order.
+    pub activities: [&'static str; EI_LABEL_COUNT + 1],
+    /// Boot check result for a component.
+    pub ok: &'static str,
+    pub fail: &'static str,
+    /// Under the long-press ring.
+    pub powering_off: &'static str,
+    /// After the step count.
+    pub steps: &'static str,
+    /// Scrolling fall-alert banner.
+    pub fall_alert: &'static str,
+    /// Clock screen before the time is set.
+    pub time_not_set: &'static str,
+    /// Marker while no IMU samples arrive.
+    pub sensor_lost: &'static str,
+    /// Shown while the button is stuck down.
+    pub button_stuck: &'static str,
+    /// IMU zeroing screen: the instruction over the countdown, the two lines
+    /// while measuring, the two on success and the three on failure.  Each
+    /// line is at most `CALIBRATION_COLS` characters.
+    pub zero_lay_flat: &'static str,
+    pub zero_measuring: &'static str,
+    pub zero_hold_still: &'static str,
+    pub zero_done: &'static str,
+    pub zero_kept: &'static str,
+    pub zero_failed: &'static str,
+    pub zero_moved: &'static str,
+    pub zero_no_imu: &'static str,
+}
+
+const ENGLISH: Strings = Strings {
+    activities: ["normal", "fall!", "walking", "running", "unknown"],
+    ok: "OK",
+    fail: "FAIL",
+    powering_off: "powering off",
+    steps: "steps",
+    fall_alert: "FALL DETECTED - PRESS TO ACK",
+    time_not_set: "time not set",
+    sensor_lost: "no IMU",
+    button_stuck: "button stuck",    zero_lay_flat: "lay flat, face up",
+    zero_measuring: "Zeroing IMU...",
+    zero_hold_still: "hold still",
+    zero_done: "IMU zeroed",
+    zero_kept: "offsets kept",
+    zero_failed: "Zeroing failed",
+    zero_moved: "watch moved or",
+    zero_no_imu: "IMU not answering",
+};
+
+const FRENCH: Strings = Strings {
+    activities: ["normal", "chute!", "marche", "course", "inconnu"],
+    ok: "OK",
+    fail: "ÉCHEC",
+    powering_off: "extinction",
+    steps: "pas",
+    fall_alert: "CHUTE DÉTECTÉE - APPUYER POUR CONFIRMER",
+    time_not_set: "heure non réglée",
+    sensor_lost: "IMU perdu",
+    button_stuck: "bouton bloqué",    zero_lay_flat: "à plat, face en haut",
+    zero_measuring: "Calibrage IMU...",
+    zero_hold_still: "ne bougez plus",
+    zero_done: "IMU calibré",
+    zero_kept: "décalages gardés",
+    zero_failed: "Calibrage échoué",
+    zero_moved: "montre bougée ou",
+    zero_no_imu: "IMU sans réponse",
+};
+
+const GERMAN: Strings = Strings {
+    activities: ["normal", "Sturz!", "Gehen", "Laufen", "unbekannt"],
+    ok: "OK",
+    fail: "FEHLER",
+    powering_off: "ausschalten",
+    steps: "Schritte",
+    fall_alert: "STURZ ERKANNT - TASTE DRÜCKEN",
+    time_not_set: "Zeit nicht gesetzt",
+    sensor_lost: "kein IMU",
+    button_stuck: "Taste klemmt",    zero_lay_flat: "flach, Display oben",
+    zero_measuring: "IMU nullen...",
+    zero_hold_still: "still halten",
+    zero_done: "IMU genullt",
+    zero_kept: "Offsets gespeichert",
+    zero_failed: "Nullen misslungen",
+    zero_moved: "Uhr bewegt oder",
+    zero_no_imu: "IMU antwortet nicht",
+};
+
+const SPANISH: Strings = Strings {
+    activities: ["normal", "¡caída!", "caminar", "correr", "desconocido"],
+    ok: "OK",
+    fail: "FALLO",
+    powering_off: "apagando",
+    steps: "pasos",
+    fall_alert: "CAÍDA DETECTADA - PULSE PARA CONFIRMAR",
+    time_not_set: "hora no ajustada",
+    sensor_lost: "sin IMU",
+    button_stuck: "botón atascado",    zero_lay_flat: "plano, boca arriba",
+    zero_measuring: "Calibrando IMU...",
+    zero_hold_still: "no se mueva",
+    zero_done: "IMU calibrada",
+    zero_kept: "ajustes guardados",
+    zero_failed: "Calibración fallida",
+    zero_moved: "reloj movido o",
+    zero_no_imu: "IMU no responde",
+};
+
+impl Language {
+    pub const fn strings(self) -> &'static Strings {
+        match self {
+            Language::English => &ENGLISH,
+            Language::French => &FRENCH,
+            Language::German => &GERMAN,
+            Language::Spanish => &SPANISH,
+        }
+    }
+}
+
+/// Strings for `UI_LANGUAGE`.
+pub fn strings() -> &'static Strings {
+    UI_LANGUAGE.strings()
+}
+
+/// Characters per line of the zeroing screen: the small font (6 px wide)
+/// inside `draw_text_block`'s margins.
+pub const CALIBRATION_COLS: usize = ((SCREEN_WIDTH - 2 * TEXT_BLOCK_MARGIN_PX) / 6) as usize;
+
+/// Whether every character of `s` is in ISO 8859-1 (U+0000–U+00FF).  In
+/// UTF-8 those are the ASCII bytes and the two-byte sequences led by 0xC2
+/// or 0xC3.
+const fn is_latin1(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            0x00..=0x7F => i += 1,
+            0xC2 | 0xC3 => i += 2,
+            _ => return false,
+        }
+    }
+    true
+}
+
+/// Whether `s` is at most `cols` characters (UTF-8 continuation bytes don't
+/// start one).
+const fn fits(s: &str, cols: usize) -> bool {
+    let bytes = s.as_bytes();
+    let mut chars = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] & 0xC0 != 0x80 {
+            chars += 1;
+        }
+        i += 1;
+    }
+    chars <= cols
+}
+
+const fn renderable(t: &Strings) -> bool {
+    let mut i = 0;
+    while i < t.activities.len() {
+        if !is_latin1(t.activities[i]) {
+            return false;
+        }
+        i += 1;
+    }
+    is_latin1(t.ok)
+        && is_latin1(t.fail)
+        && is_latin1(t.powering_off)
+        && is_latin1(t.steps)
+        && is_latin1(t.fall_alert)
+        && is_latin1(t.time_not_set)
+        && is_latin1(t.sensor_lost)
+        && is_latin1(t.button_stuck)
+        && zero_line(t.zero_lay_flat)
+        && zero_line(t.zero_measuring)
+        && zero_line(t.zero_hold_still)
+        && zero_line(t.zero_done)
+        && zero_line(t.zero_kept)
+        && zero_line(t.zero_failed)
+        && zero_line(t.zero_moved)
+        && zero_line(t.zero_no_imu)
+}
+
+const fn zero_line(s: &str) -> bool {
+    is_latin1(s) && fits(s, CALIBRATION_COLS)
+}
+
+const _: () = assert!(renderable(&ENGLISH));
+const _: () = assert!(renderable(&FRENCH));
+const _: () = assert!(renderable(&GERMAN));
+const _: () = assert!(renderable(&SPANISH));
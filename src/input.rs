@@ -1,52 +1,157 @@
 // PlastiWatch V2 — Button Input Manager
 //
-// Debounced button handler with single-click, double-click, and long-press
-// detection.  Designed to be polled at ~100 Hz from the UI task.
-
-use std::sync::mpsc::Sender;
-use std::time::Instant;
+// Debounced button handler with single-, double- and triple-click plus
+// long-press detection.  Designed to be polled at ~100 Hz from the UI task.
+//
+// Short clicks are counted; each one reopens the double-click window.  The
+// click event is only sent once that window lapses with no further press (or
+// at the third click, since nothing follows it), so a double-click never
+// emits a single-click first.
+//
+// While the button is held past `HOLD_PROGRESS_START_MS`, `ButtonHoldProgress`
+// events (0.0–1.0 of the long-press time) are sent every
+// `HOLD_PROGRESS_INTERVAL_MS` so the UI can draw a power-off ring.  Letting go
+// early sends `ButtonHoldCancelled` instead of a click.
+//
+// With `repeat_enabled`, holding the button also sends `ButtonRepeat` every
+// `repeat_interval_ms` after `repeat_delay_ms` (key auto-repeat, for menu
+// scrolling) until the hold reaches the long press, which then takes over.
+//
+// A button that stays pressed for `BUTTON_STUCK_MS` — shorted or jammed, since
+// nobody holds it that long on purpose — is a fault: `ButtonStuck` is sent
+// once and the hold produces nothing else (no progress ring, repeats, or long
+// press on release), so a fault can't keep asking for sleep.  The first clean
+// release sends `ButtonRecovered` and ends the fault.
+//
+// Debounce, long-press and click-window timings come from an `InputConfig`
+// so they can be changed at runtime (e.g. from a settings screen) without
+// reflashing.
+//
+// The manager owns no hardware or clock of its own: it reads the button
+// through `ButtonInput`, sends through `EventSink` and takes the time from a
+// `ticks::Clock`.  The firmware plugs in the GPIO pin, the UI event broadcast
+// and `SystemClock`; the host tests (host-tests/) plug in a scripted pin, a
+// recording sink and a `MockClock`, and check the exact events a press
+// sequence produces.  The GPIO and broadcast impls are left out of test
+// builds.
 
+#[cfg(not(test))]
 use esp_idf_hal::gpio::{AnyInputPin, Input, PinDriver};
 
+#[cfg(not(test))]
+use crate::broadcast::Broadcast;
 use crate::config::*;
 use crate::events::UiEvent;
+use crate::ticks::{self, Clock};
+
+/// Reads the button's level.
+pub trait ButtonInput {
+    /// Whether the button reads pressed right now (before debouncing).
+    fn is_pressed(&mut self) -> bool;
+}
+
+#[cfg(not(test))]
+impl ButtonInput for PinDriver<'_, AnyInputPin, Input> {
+    fn is_pressed(&mut self) -> bool {
+        self.is_low() // pull-up → active LOW
+    }
+}
+
+/// Receives the events `InputManager` produces.
+pub trait EventSink {
+    fn send(&self, event: UiEvent);
+}
+
+#[cfg(not(test))]
+impl EventSink for Broadcast<UiEvent> {
+    fn send(&self, event: UiEvent) {
+        let _ = Broadcast::send(self, event);
+    }
+}
+
+/// Button timing parameters (milliseconds).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputConfig {
+    pub debounce_ms: u64,
+    pub long_press_ms: u64,
+    pub double_click_window_ms: u64,
+    pub repeat_enabled: bool,
+    pub repeat_delay_ms: u64,
+    pub repeat_interval_ms: u64,
+}
 
-pub struct InputManager<'d> {
-    pin: PinDriver<'d, AnyInputPin, Input>,
-    ui_tx: Sender<UiEvent>,
+impl Default for InputConfig {
+    fn default() -> Self {
+        Self {
+            debounce_ms: DEBOUNCE_MS,
+            long_press_ms: LONG_PRESS_MS,
+            double_click_window_ms: DOUBLE_CLICK_WINDOW_MS,
+            repeat_enabled: false,
+            repeat_delay_ms: BUTTON_REPEAT_DELAY_MS,
+            repeat_interval_ms: BUTTON_REPEAT_INTERVAL_MS,
+        }
+    }
+}
 
-    // Debounce state
+/// Milliseconds from `then` to `now`, both `now_ms()` readings.
+fn elapsed(now: u32, then: u32) -> u64 {
+    ticks::elapsed_ms(now, then) as u64
+}
+
+pub struct InputManager<P, S, C> {
+    pin: P,
+    ui_tx: S,
+    clock: C,
+    config: InputConfig,
+
+    // Debounce state (times are `now_ms()` readings)
     last_raw: bool,
-    last_debounce: Instant,
+    last_debounce: u32,
 
     // Press tracking
-    press_start: Option<Instant>,
+    press_start: Option<u32>,
     button_down: bool,
+    last_progress: Option<(u32, f32)>, // last ButtonHoldProgress sent
+    last_repeat: Option<u32>,
+    stuck: bool,
 
-    // Double-click state machine
-    waiting_for_second_click: bool,
-    first_click_time: Instant,
+    // Multi-click state machine
+    click_count: u8,
+    last_click_time: u32,
 }
 
-impl<'d> InputManager<'d> {
-    pub fn new(pin: PinDriver<'d, AnyInputPin, Input>, ui_tx: Sender<UiEvent>) -> Self {
-        let now = Instant::now();
+impl<P: ButtonInput, S: EventSink, C: Clock> InputManager<P, S, C> {
+    pub fn new(pin: P, ui_tx: S, clock: C, config: InputConfig) -> Self {
         Self {
             pin,
             ui_tx,
-            last_raw: true, // pull-up → idle HIGH
-            last_debounce: now,
+            clock,
+            config,
+            last_raw: false, // idle = released
+            last_debounce: 0,
             press_start: None,
             button_down: false,
-            waiting_for_second_click: false,
-            first_click_time: now,
+            last_progress: None,
+            last_repeat: None,
+            stuck: false,
+            click_count: 0,
+            last_click_time: 0,
         }
     }
 
+    pub fn config(&self) -> InputConfig {
+        self.config
+    }
+
+    /// Replace the timing parameters; takes effect from the next `update`.
+    pub fn set_config(&mut self, config: InputConfig) {
+        self.config = config;
+    }
+
     /// Call every ~10 ms from the UI task loop.
     pub fn update(&mut self) {
-        let current = self.pin.is_high(); // true = released (pull-up)
-        let now = Instant::now();
+        let current = self.pin.is_pressed();
+        let now = self.clock.now_ms();
 
         // ---- debounce filter ----
         if current != self.last_raw {
@@ -54,14 +159,13 @@ impl<'d> InputManager<'d> {
         }
         self.last_raw = current;
 
-        let stable_ms = now.duration_since(self.last_debounce).as_millis() as u64;
-        if stable_ms < DEBOUNCE_MS {
+        if elapsed(now, self.last_debounce) < self.config.debounce_ms {
             // Signal still bouncing — wait.
             self.check_double_click_timeout(now);
             return;
         }
 
-        let pressed = !current; // active LOW
+        let pressed = current;
 
         // ---- button pressed edge ----
         if pressed && !self.button_down {
@@ -70,38 +174,128 @@ impl<'d> InputManager<'d> {
         }
 
         // ---- button released edge ----
+        if !pressed && self.button_down && std::mem::take(&mut self.stuck) {
+            log::info!("Button released — stuck-button fault cleared");
+            self.ui_tx.send(UiEvent::ButtonRecovered);
+            self.button_down = false;
+            self.click_count = 0;
+            self.last_progress = None;
+            self.last_repeat = None;
+        }
         if !pressed && self.button_down {
             self.button_down = false;
-            let hold_ms = self
-                .press_start
-                .map(|t| now.duration_since(t).as_millis() as u64)
-                .unwrap_or(0);
-
-            if hold_ms >= LONG_PRESS_MS {
-                let _ = self.ui_tx.send(UiEvent::ButtonLongPress);
-                self.waiting_for_second_click = false;
-            } else if self.waiting_for_second_click {
-                // Second click within window → double-click
-                let _ = self.ui_tx.send(UiEvent::ButtonDoubleClick);
-                self.waiting_for_second_click = false;
+            let hold_ms = self.press_start.map_or(0, |t| elapsed(now, t));
+
+            if hold_ms >= self.config.long_press_ms {
+                self.ui_tx.send(UiEvent::ButtonLongPress);
+                self.click_count = 0;
+            } else if self.last_progress.is_some() {
+                // Released after the power-off ring appeared — cancel, no click
+                self.ui_tx.send(UiEvent::ButtonHoldCancelled);
+                self.click_count = 0;
             } else {
-                // First short click — start double-click window
-                self.waiting_for_second_click = true;
-                self.first_click_time = now;
+                // Short click — count it and (re)open the click window
+                self.click_count += 1;
+                self.last_click_time = now;
+                if self.click_count == 3 {
+                    self.ui_tx.send(UiEvent::ButtonTripleClick);
+                    self.click_count = 0;
+                }
+            }
+            self.last_progress = None;
+            self.last_repeat = None;
+        }
+
+        // ---- stuck check, hold progress (throttled) + auto-repeat ----
+        if self.button_down && !self.stuck {
+            self.check_stuck(now);
+        }
+        if self.button_down && !self.stuck {
+            self.report_hold_progress(now);
+            if self.config.repeat_enabled {
+                self.check_repeat(now);
             }
         }
 
         self.check_double_click_timeout(now);
     }
 
-    /// If the double-click window expires, emit a single-click.
-    fn check_double_click_timeout(&mut self, now: Instant) {
-        if self.waiting_for_second_click {
-            let elapsed = now.duration_since(self.first_click_time).as_millis() as u64;
-            if elapsed > DOUBLE_CLICK_WINDOW_MS {
-                let _ = self.ui_tx.send(UiEvent::ButtonSingleClick);
-                self.waiting_for_second_click = false;
+    /// Declare a stuck-button fault once the hold reaches `BUTTON_STUCK_MS`.
+    fn check_stuck(&mut self, now: u32) {
+        let Some(start) = self.press_start else {
+            return;
+        };
+        let hold_ms = elapsed(now, start);
+        if hold_ms >= BUTTON_STUCK_MS {
+            log::error!(
+                "Button pressed for {} s — stuck, ignoring it until released",
+                hold_ms / 1000
+            );
+            self.stuck = true;
+            self.click_count = 0;
+            self.ui_tx.send(UiEvent::ButtonStuck);
+        }
+    }
+
+    /// Send `ButtonHoldProgress` at most every `HOLD_PROGRESS_INTERVAL_MS`
+    /// once the hold passes `HOLD_PROGRESS_START_MS`, finishing with 1.0.
+    fn report_hold_progress(&mut self, now: u32) {
+        let Some(start) = self.press_start else {
+            return;
+        };
+        let hold_ms = elapsed(now, start);
+        if hold_ms < HOLD_PROGRESS_START_MS {
+            return;
+        }
+
+        let due = match self.last_progress {
+            None => true,
+            Some((t, sent)) => {
+                sent < 1.0 && elapsed(now, t) >= HOLD_PROGRESS_INTERVAL_MS
             }
+        };
+        if due {
+            let progress = (hold_ms as f32 / self.config.long_press_ms as f32).min(1.0);
+            self.ui_tx.send(UiEvent::ButtonHoldProgress(progress));
+            self.last_progress = Some((now, progress));
+        }
+    }
+
+    /// Send `ButtonRepeat` after the initial delay, then every interval, for
+    /// as long as the hold stays short of a long press.
+    fn check_repeat(&mut self, now: u32) {
+        let Some(start) = self.press_start else {
+            return;
+        };
+        let hold_ms = elapsed(now, start);
+        if hold_ms < self.config.repeat_delay_ms || hold_ms >= self.config.long_press_ms {
+            return;
+        }
+
+        let due = self
+            .last_repeat
+            .map_or(true, |t| elapsed(now, t) >= self.config.repeat_interval_ms);
+        if due {
+            self.ui_tx.send(UiEvent::ButtonRepeat);
+            self.last_repeat = Some(now);
+        }
+    }
+
+    /// If the click window expires with no further press, emit a single- or
+    /// double-click for the clicks counted so far.
+    fn check_double_click_timeout(&mut self, now: u32) {
+        if self.click_count == 0 || self.button_down {
+            return;
+        }
+
+        if elapsed(now, self.last_click_time) > self.config.double_click_window_ms {
+            let event = if self.click_count == 1 {
+                UiEvent::ButtonSingleClick
+            } else {
+                UiEvent::ButtonDoubleClick
+            };
+            self.ui_tx.send(event);
+            self.click_count = 0;
         }
     }
 }
@@ -1,37 +1,74 @@
 // PlastiWatch V2 — Firmware Entry Point
 //
 // Boot sequence:
-//   1. Wait for the user button to be held for 3 seconds (boot trigger).
+//   1. Wait for the user button to be held for 3 seconds (boot trigger),
+//      unless this is a button or motion wake from deep sleep.
 //   2. Display the PlastiBytes logo for 1 second.
 //   3. Display "PlastiWatch" text for 1 second.
-//   4. Run component self-test (OLED + MPU6050).
+//   4. Run component self-test (OLED + MPU6050, retried a few times) and
+//      show the result for 1 second.
+//      The durations are in config.rs; with `BOOT_SPLASH_SKIP_ON_PRESS` a
+//      press advances past any of these screens early.
 //   5. Enter default UI (logo + "PlastiBytes" label).
-//   6. Spawn sensor, AI, UI, and power tasks.
+//   6. Restore persisted state (steps, settings) from NVS.
+//   7. Spawn sensor, AI, UI, and power tasks.
 //
 // The system enters deep sleep when:
 //   - The user holds the button for 3 seconds (long-press).
-//   - No activity is detected for 3 minutes.
-
+//   - No activity is detected for `DEEP_SLEEP_TIMEOUT_MS` (3 minutes; the
+//     screen dims and switches off before that).
+// Either way the power task raises `sleep_requested`, the UI task stops the
+// motor and blanks the OLED, then acknowledges via `sleep_ready` before the
+// chip powers down.  Wake sources are set up in `sleep` (button, motion,
+// optional timer); the boot-trigger sleep leaves out motion because the IMU
+// hasn't been armed yet.
+
+mod battery;
+#[cfg(feature = "ble")]
+mod ble;
+mod broadcast;
+mod clock;
+#[cfg(feature = "capture")]
+mod capture;
 mod config;
+mod decision;
 mod drivers;
+mod dwell;
 mod ei;
 mod events;
+mod filter;
+mod history;
+mod i18n;
 mod input;
+mod nvs;
+mod orientation;
+mod pedometer;
+mod reset;
+mod sleep;
+mod smoothing;
+mod state;
 mod tasks;
+mod ticks;
+mod watchdog;
+mod wear;
 
-use std::sync::atomic::{AtomicBool, AtomicU32};
+use std::sync::atomic::{AtomicBool, AtomicU64};
 use std::sync::mpsc;
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 
-use esp_idf_hal::gpio::{AnyInputPin, AnyOutputPin, IOPin, Input, InputPin, Output, OutputPin, Pin, PinDriver};
-use esp_idf_hal::i2c::{I2cConfig, I2cDriver};
+use esp_idf_hal::gpio::{AnyInputPin, IOPin, Input, InputPin, Pin, PinDriver};
+use esp_idf_hal::i2c::I2cDriver;
+use esp_idf_hal::ledc::{config::TimerConfig, LedcDriver, LedcTimerDriver};
 use esp_idf_hal::prelude::*;
 
+use crate::broadcast::Broadcast;
 use crate::config::*;
 use crate::drivers::display::OledDisplay;
 use crate::drivers::imu::Mpu6050;
+use crate::sleep::SleepConfig;
+use crate::ticks::SystemClock;
 
 // ---------------------------------------------------------------------------
 // Utility: milliseconds since boot (wraps at ~49 days — fine for timeouts)
@@ -48,6 +85,7 @@ fn main() -> anyhow::Result<()> {
     esp_idf_svc::sys::link_patches();
     esp_idf_svc::log::EspLogger::initialize_default();
     log::info!("PlastiWatch V2 firmware starting…");
+    log::info!("Reset reason: {}", reset::reason().display_name());
 
     // ---- Peripherals ------------------------------------------------------
     let peripherals = Peripherals::take()?;
@@ -57,24 +95,25 @@ fn main() -> anyhow::Result<()> {
     configure_pullup(&button);
 
     // ---- Boot trigger: hold button for 3 seconds --------------------------
-    if !wait_for_boot_hold(&button) {
+    // Not needed after a button/motion wake: the wearer already asked.
+    if reset::woken_by_gpio() {
+        log::info!("Woken from deep sleep — skipping boot hold");
+    } else if !wait_for_boot_hold(&button) {
         log::info!("Boot trigger not met — entering deep sleep");
-        enter_deep_sleep();
+        // The IMU hasn't been armed on this path, so no motion wake.
+        sleep::enter_deep_sleep(SleepConfig::from_config().motion(false));
     }
     log::info!("Boot trigger confirmed");
 
     // ---- I2C bus (shared between OLED and MPU6050) ------------------------
-    let i2c_config = I2cConfig::new().baudrate(400u32.kHz().into());
-    let i2c = I2cDriver::new(
-        peripherals.i2c0,
-        peripherals.pins.gpio6, // SDA
-        peripherals.pins.gpio7, // SCL
-        &i2c_config,
-    )?;
-    // SAFETY: The I2C peripheral is a singleton obtained from `Peripherals::take()`.
-    // It will live for the entire programme duration (embedded firmware never exits).
-    let i2c_bus: &'static Mutex<I2cDriver<'static>> =
-        Box::leak(Box::new(Mutex::new(unsafe { core::mem::transmute(i2c) })));
+    // Pins and speed come from config.rs (`PIN_I2C_*`, `I2C_BAUD_HZ`).
+    let i2c = drivers::bus::open(peripherals.i2c0)?;
+    // Leaked so every driver can hold a `'static` reference; it lives for the
+    // entire programme duration (embedded firmware never exits).
+    let i2c_bus: &'static Mutex<I2cDriver<'static>> = Box::leak(Box::new(Mutex::new(i2c)));
+
+    scan_i2c_bus(i2c_bus);
+    clock::init(i2c_bus);
 
     // ---- Boot sequence (display) ------------------------------------------
     let mut display = OledDisplay::new(i2c_bus);
@@ -82,19 +121,20 @@ fn main() -> anyhow::Result<()> {
 
     // Step 1 — PlastiBytes logo splash
     display.show_logo()?;
-    thread::sleep(Duration::from_millis(BOOT_LOGO_DISPLAY_MS));
+    wait_or_skip(&button, BOOT_LOGO_DISPLAY_MS);
 
     // Step 2 — "PlastiWatch" text splash
     display.show_centered_text("PlastiWatch")?;
-    thread::sleep(Duration::from_millis(BOOT_TEXT_DISPLAY_MS));
+    wait_or_skip(&button, BOOT_TEXT_DISPLAY_MS);
 
-    // Step 3 — Component self-test
-    let oled_ok = display.is_connected();
+    // Step 3 — Component self-test.  A cold bus sometimes NACKs the very
+    // first transaction, so only repeated failures count.
+    let oled_ok = self_test("OLED", || display.is_connected());
     let imu = Mpu6050::new(i2c_bus);
-    let imu_ok = imu.is_connected();
+    let imu_ok = self_test("IMU", || imu.is_connected());
 
     display.show_boot_status(oled_ok, imu_ok)?;
-    thread::sleep(Duration::from_secs(1));
+    wait_or_skip(&button, BOOT_STATUS_DISPLAY_MS);
 
     if !oled_ok || !imu_ok {
         log::error!("Boot check FAILED — OLED:{} IMU:{}", oled_ok, imu_ok);
@@ -105,13 +145,33 @@ fn main() -> anyhow::Result<()> {
     display.show_default_ui()?;
     log::info!("Boot complete — entering normal operation");
 
+    // ---- Persistent state (survives deep sleep) ---------------------------
+    let mut store = match nvs::Store::open() {
+        Ok(s) => Some(s),
+        Err(e) => {
+            log::warn!("NVS unavailable ({}) — state will not persist", e);
+            None
+        }
+    };
+    let saved_state = store.as_mut().map(|s| s.load()).unwrap_or_default();
+    if let Some(s) = store.as_mut() {
+        s.load_history();
+    }
+    let persistent = Arc::new(Mutex::new(saved_state));
+
     // ---- Channels ---------------------------------------------------------
     let (sensor_tx, sensor_rx) = mpsc::channel();
-    let (ui_tx, ui_rx) = mpsc::channel();
+    // UI events fan out to every subscriber (the UI task, BLE, …).
+    let ui_tx = Broadcast::new();
+    let ui_rx = ui_tx.subscribe();
+    #[cfg(feature = "ble")]
+    let ble_rx = ui_tx.subscribe();
 
     // ---- Shared state -----------------------------------------------------
     let sleep_requested = Arc::new(AtomicBool::new(false));
-    let last_activity_ms = Arc::new(AtomicU32::new(now_ms()));
+    let sleep_ready = Arc::new(AtomicBool::new(false));
+    let state = Arc::new(Mutex::new(state::SystemState::new(saved_state.steps)));
+    let sample_interval_ms = Arc::new(AtomicU64::new(SENSOR_SAMPLE_INTERVAL_MS));
 
     // ---- Prepare GPIO handles for tasks -----------------------------------
     // Re-use the button PinDriver (already configured) — extend to 'static.
@@ -119,35 +179,61 @@ fn main() -> anyhow::Result<()> {
     let button_static: PinDriver<'static, AnyInputPin, Input> =
         unsafe { core::mem::transmute(button) };
 
-    let haptic_pin = PinDriver::output(peripherals.pins.gpio4.downgrade_output())?;
-    let haptic_static: PinDriver<'static, AnyOutputPin, Output> =
-        unsafe { core::mem::transmute(haptic_pin) };
+    let haptic_timer = LedcTimerDriver::new(
+        peripherals.ledc.timer0,
+        &TimerConfig::new().frequency(HAPTIC_PWM_FREQ_HZ.Hz()),
+    )?;
+    let haptic_pwm = LedcDriver::new(peripherals.ledc.channel0, haptic_timer, peripherals.pins.gpio4)?;
+    let haptic_static: LedcDriver<'static> = unsafe { core::mem::transmute(haptic_pwm) };
 
     // ---- Spawn tasks (map to FreeRTOS tasks via std::thread) ---------------
 
+    // Tasks subscribe themselves; configure the watchdog before any start.
+    watchdog::init();
+
     // Sensor task — highest effective priority (tightest timing).
     let sensor_bus = i2c_bus;
+    let sensor_interval = Arc::clone(&sample_interval_ms);
+    let sensor_state = Arc::clone(&state);
+    let sensor_ui_tx = ui_tx.clone();
     thread::Builder::new()
         .name("sensor".into())
         .stack_size(STACK_SENSOR)
         .spawn(move || {
-            tasks::sensor::sensor_task(sensor_bus, sensor_tx);
+            tasks::sensor::sensor_task(
+                sensor_bus,
+                sensor_tx,
+                sensor_interval,
+                sensor_state,
+                sensor_ui_tx,
+                saved_state.imu_offsets,
+            );
         })?;
 
     // AI inference task
     let ai_ui_tx = ui_tx.clone();
-    let ai_activity = Arc::clone(&last_activity_ms);
+    let ai_state = Arc::clone(&state);
     thread::Builder::new()
         .name("ai".into())
         .stack_size(STACK_AI)
         .spawn(move || {
-            tasks::ai::ai_task(sensor_rx, ai_ui_tx, ai_activity);
+            tasks::ai::ai_task(
+                sensor_rx,
+                ai_ui_tx,
+                ai_state,
+                sample_interval_ms,
+                saved_state.steps,
+                EI_WINDOW_SAMPLES,
+                SystemClock,
+            );
         })?;
 
     // UI task (display + button + haptic)
     let ui_sleep = Arc::clone(&sleep_requested);
-    let ui_activity = Arc::clone(&last_activity_ms);
+    let ui_sleep_ready = Arc::clone(&sleep_ready);
+    let ui_state = Arc::clone(&state);
     let ui_tx_for_input = ui_tx.clone();
+    let ui_persistent = Arc::clone(&persistent);
     thread::Builder::new()
         .name("ui".into())
         .stack_size(STACK_UI)
@@ -159,18 +245,50 @@ fn main() -> anyhow::Result<()> {
                 ui_rx,
                 ui_tx_for_input,
                 ui_sleep,
-                ui_activity,
+                ui_sleep_ready,
+                ui_state,
+                ui_persistent,
             );
         })?;
 
+    // BLE status service, fed from its own subscription.
+    #[cfg(feature = "ble")]
+    match ble::init(&state) {
+        Ok(()) => {
+            thread::Builder::new()
+                .name("ble".into())
+                .stack_size(STACK_BLE)
+                .spawn(move || {
+                    for event in ble_rx {
+                        ble::publish(&event);
+                    }
+                })?;
+        }
+        Err(e) => {
+            log::warn!("BLE unavailable ({}) — continuing without it", e);
+            // Unsubscribe: nothing would ever read it, and events would pile up.
+            drop(ble_rx);
+        }
+    }
+
+    // Serial console (tuning and diagnostics)
+    let console_ui_tx = ui_tx.clone();
+    let console_state = Arc::clone(&state);
+    let console_sleep = Arc::clone(&sleep_requested);
+    thread::Builder::new()
+        .name("console".into())
+        .stack_size(STACK_CONSOLE)
+        .spawn(move || {
+            tasks::console::console_task(console_ui_tx, console_state, console_sleep);
+        })?;
+
     // Power management task
     let pwr_sleep = Arc::clone(&sleep_requested);
-    let pwr_activity = Arc::clone(&last_activity_ms);
     thread::Builder::new()
         .name("power".into())
         .stack_size(STACK_POWER)
         .spawn(move || {
-            tasks::power::power_task(ui_tx, pwr_sleep, pwr_activity);
+            tasks::power::power_task(ui_tx, pwr_sleep, sleep_ready, state, store, persistent);
         })?;
 
     // Main thread has nothing left to do — park it forever.
@@ -212,6 +330,58 @@ fn wait_for_boot_hold(button: &PinDriver<'_, AnyInputPin, Input>) -> bool {
     }
 }
 
+/// Show a boot screen for up to `ms`.  With [`BOOT_SPLASH_SKIP_ON_PRESS`] a
+/// new press (released → pressed) ends the wait early; a button still held
+/// from the boot trigger doesn't count until it has been released.
+fn wait_or_skip(button: &PinDriver<'_, AnyInputPin, Input>, ms: u64) {
+    let start = std::time::Instant::now();
+    let duration = Duration::from_millis(ms);
+    let poll = Duration::from_millis(10);
+    let mut was_pressed = button.is_low();
+
+    while start.elapsed() < duration {
+        let pressed = button.is_low();
+        if BOOT_SPLASH_SKIP_ON_PRESS && pressed && !was_pressed {
+            log::info!("Boot screen skipped");
+            return;
+        }
+        was_pressed = pressed;
+        thread::sleep(poll);
+    }
+}
+
+/// Probe every 7-bit I2C address once and log the ones that ACK.
+/// Makes "is it even wired right" obvious from the boot log.
+fn scan_i2c_bus(bus: &Mutex<I2cDriver<'static>>) {
+    let mut bus = bus.lock().unwrap();
+    let mut found = 0;
+    for addr in 0x08u8..=0x77 {
+        if bus.write(addr, &[], I2C_SCAN_TIMEOUT_TICKS).is_ok() {
+            log::info!("I2C scan: device at 0x{:02X}", addr);
+            found += 1;
+        }
+    }
+    log::info!("I2C scan complete — {} device(s) found", found);
+}
+
+/// Run a connectivity `check` up to [`SELF_TEST_ATTEMPTS`] times, pausing
+/// [`SELF_TEST_RETRY_DELAY_MS`] between tries.  `true` as soon as one passes.
+fn self_test(name: &str, check: impl Fn() -> bool) -> bool {
+    for attempt in 1..=SELF_TEST_ATTEMPTS {
+        if check() {
+            if attempt > 1 {
+                log::info!("Self-test: {} answered on attempt {}", name, attempt);
+            }
+            return true;
+        }
+        if attempt < SELF_TEST_ATTEMPTS {
+            thread::sleep(Duration::from_millis(SELF_TEST_RETRY_DELAY_MS));
+        }
+    }
+    log::warn!("Self-test: {} not responding after {} attempts", name, SELF_TEST_ATTEMPTS);
+    false
+}
+
 /// Configure internal pull-up on a PinDriver.  Separated because the borrow
 /// checker needs a helper for the downgraded pin type.
 fn configure_pullup(_pin: &PinDriver<'_, AnyInputPin, Input>) {
@@ -226,14 +396,3 @@ fn configure_pullup(_pin: &PinDriver<'_, AnyInputPin, Input>) {
         );
     }
 }
-
-/// Enter deep sleep with button-press wakeup.  Does not return.
-fn enter_deep_sleep() -> ! {
-    unsafe {
-        esp_idf_sys::esp_deep_sleep_enable_gpio_wakeup(
-            1u64 << PIN_BUTTON,
-            esp_idf_sys::esp_deepsleep_gpio_wake_up_mode_t_ESP_GPIO_WAKEUP_GPIO_LOW,
-        );
-        esp_idf_sys::esp_deep_sleep_start();
-    }
-}
@@ -0,0 +1,193 @@
+// PlastiWatch V2 — Persistent State (NVS)
+//
+// Deep sleep wipes RAM, so anything that should outlive it — the step count,
+// the last reported activity, the user's button timings and display
+// settings, the IMU zero-point offsets from the last successful calibration —
+// is kept in a `PersistentState` and stored in the NVS flash partition as one
+// versioned blob under the "plastiwatch" namespace.
+//
+// `main` loads it on boot; on first boot (empty namespace), after a format
+// change or if the blob is unreadable the defaults are used.  The power task
+// saves it just before deep sleep, and only when it differs from what is
+// already in flash, so normal use costs one flash write per sleep at most.
+//
+// The activity history (`history`) is stored next to it under its own key.
+
+use esp_idf_svc::nvs::{EspDefaultNvs, EspDefaultNvsPartition};
+
+use crate::config::*;
+use crate::drivers::imu::CalibrationOffsets;
+use crate::events::ActivityClass;
+use crate::history;
+use crate::input::InputConfig;
+
+const NAMESPACE: &str = "plastiwatch";
+const KEY_STATE: &str = "state";
+const KEY_HISTORY: &str = "history";
+
+/// Bump when the blob layout changes; older blobs are then ignored.
+const FORMAT_VERSION: u8 = 3;
+const BLOB_LEN: usize = 44;
+
+/// Everything that survives deep sleep.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PersistentState {
+    pub steps: u32,
+    pub last_activity: ActivityClass,
+    pub input: InputConfig,
+    pub brightness: u8,
+    pub inverted: bool,
+    /// Applied by the sensor task at boot; `None` until calibrated once.
+    pub imu_offsets: Option<CalibrationOffsets>,
+}
+
+impl Default for PersistentState {
+    fn default() -> Self {
+        Self {
+            steps: 0,
+            last_activity: ActivityClass::default(),
+            input: InputConfig::default(),
+            brightness: OLED_DEFAULT_BRIGHTNESS,
+            inverted: false,
+            imu_offsets: None,
+        }
+    }
+}
+
+impl PersistentState {
+    fn encode(&self) -> [u8; BLOB_LEN] {
+        let ms = |v: u64| (v.min(u16::MAX as u64) as u16).to_le_bytes();
+
+        let mut blob = [0u8; BLOB_LEN];
+        blob[0] = FORMAT_VERSION;
+        blob[1..5].copy_from_slice(&self.steps.to_le_bytes());
+        blob[5] = self.last_activity.index() as u8;
+        blob[6] = self.brightness;
+        blob[7] = self.input.repeat_enabled as u8;
+        blob[8..10].copy_from_slice(&ms(self.input.debounce_ms));
+        blob[10..12].copy_from_slice(&ms(self.input.long_press_ms));
+        blob[12..14].copy_from_slice(&ms(self.input.double_click_window_ms));
+        blob[14..16].copy_from_slice(&ms(self.input.repeat_delay_ms));
+        blob[16..18].copy_from_slice(&ms(self.input.repeat_interval_ms));
+        blob[18] = self.inverted as u8;
+        if let Some(o) = self.imu_offsets {
+            blob[19] = 1;
+            for (i, v) in [o.ax, o.ay, o.az, o.gx, o.gy, o.gz].iter().enumerate() {
+                blob[20 + 4 * i..24 + 4 * i].copy_from_slice(&v.to_le_bytes());
+            }
+        }
+        blob
+    }
+
+    fn decode(blob: &[u8]) -> Option<Self> {
+        if blob.len() != BLOB_LEN || blob[0] != FORMAT_VERSION {
+            return None;
+        }
+        let ms = |i: usize| u16::from_le_bytes([blob[i], blob[i + 1]]) as u64;
+        let f = |i: usize| f32::from_le_bytes([blob[i], blob[i + 1], blob[i + 2], blob[i + 3]]);
+
+        Some(Self {
+            steps: u32::from_le_bytes(blob[1..5].try_into().ok()?),
+            last_activity: ActivityClass::from_index(blob[5] as usize),
+            brightness: blob[6],
+            inverted: blob[18] != 0,
+            input: InputConfig {
+                repeat_enabled: blob[7] != 0,
+                debounce_ms: ms(8),
+                long_press_ms: ms(10),
+                double_click_window_ms: ms(12),
+                repeat_delay_ms: ms(14),
+                repeat_interval_ms: ms(16),
+            },
+            imu_offsets: (blob[19] != 0).then(|| CalibrationOffsets {
+                ax: f(20),
+                ay: f(24),
+                az: f(28),
+                gx: f(32),
+                gy: f(36),
+                gz: f(40),
+            }),
+        })
+    }
+}
+
+/// Handle on the NVS namespace.
+pub struct Store {
+    nvs: EspDefaultNvs,
+    /// Blob currently in flash, to skip redundant writes.
+    saved: Option<[u8; BLOB_LEN]>,
+}
+
+impl Store {
+    /// Open (creating if needed) the firmware's namespace in the default
+    /// NVS partition.
+    pub fn open() -> anyhow::Result<Self> {
+        let partition = EspDefaultNvsPartition::take()?;
+        let nvs = EspDefaultNvs::new(partition, NAMESPACE, true)?;
+        Ok(Self { nvs, saved: None })
+    }
+
+    /// Stored state, or the defaults if there is none (first boot) or it
+    /// can't be read.
+    pub fn load(&mut self) -> PersistentState {
+        let mut buf = [0u8; BLOB_LEN];
+        match self.nvs.get_blob(KEY_STATE, &mut buf) {
+            Ok(Some(blob)) => match PersistentState::decode(blob) {
+                Some(state) => {
+                    self.saved = Some(state.encode());
+                    log::info!("NVS: restored {:?}", state);
+                    state
+                }
+                None => {
+                    log::warn!("NVS: stored state has an unknown format — using defaults");
+                    PersistentState::default()
+                }
+            },
+            Ok(None) => {
+                log::info!("NVS: no saved state (first boot) — using defaults");
+                PersistentState::default()
+            }
+            Err(e) => {
+                log::warn!("NVS: read failed ({}) — using defaults", e);
+                PersistentState::default()
+            }
+        }
+    }
+
+    /// Write `state` to flash unless it matches what is already there.
+    pub fn save(&mut self, state: &PersistentState) -> anyhow::Result<()> {
+        let blob = state.encode();
+        if self.saved == Some(blob) {
+            return Ok(());
+        }
+        self.nvs.set_blob(KEY_STATE, &blob)?;
+        self.saved = Some(blob);
+        log::info!("NVS: saved {:?}", state);
+        Ok(())
+    }
+
+    /// Load the activity history log saved by [`Self::save_history`].
+    pub fn load_history(&mut self) {
+        let mut buf = vec![0u8; history::BLOB_MAX];
+        match self.nvs.get_blob(KEY_HISTORY, &mut buf) {
+            Ok(Some(blob)) => {
+                if !history::restore(blob) {
+                    log::warn!("NVS: stored history has an unknown format — starting empty");
+                }
+            }
+            Ok(None) => {}
+            Err(e) => log::warn!("NVS: history read failed ({})", e),
+        }
+    }
+
+    /// Write the activity history if anything was recorded since the last
+    /// write.
+    pub fn save_history(&mut self) -> anyhow::Result<()> {
+        if let Some((blob, token)) = history::unflushed_blob() {
+            self.nvs.set_blob(KEY_HISTORY, &blob)?;
+            history::mark_flushed(token);
+            log::debug!("NVS: saved {} bytes of history", blob.len());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,180 @@
+// PlastiWatch V2 — Wrist Orientation
+//
+// Pitch / roll from the accelerometer gravity vector, plus a "raise to wake"
+// gesture detector.
+//
+// The raise is recognised as a motion, not an angle: the smoothed roll must
+// climb from below `WRIST_RAISE_ROLL_DEG − WRIST_RAISE_HYSTERESIS_DEG` to
+// above `WRIST_RAISE_ROLL_DEG` within `WRIST_RAISE_MAX_DURATION_MS` (a slow
+// drift while lying down doesn't count), and the wrist must then hold steady
+// there — |a| within `WRIST_RAISE_STILL_G` of 1 g — for
+// `WRIST_RAISE_SETTLE_MS`.  An arm swinging while walking passes through the
+// same angles but never settles, so it doesn't wake the screen.
+//
+// `ScreenOrientationDetector` decides which edge of the screen is up from the
+// gravity component along the panel's vertical (IMU X, signed by
+// `ORIENTATION_UP_SIGN`), so the UI can flip the display 180° when the watch
+// is clasped the other way round.  Between ±`ORIENTATION_FLIP_G` (watch
+// roughly flat) the current orientation holds, and a new one must persist for
+// `ORIENTATION_DEBOUNCE_MS` before it is reported.  Only 180° is supported:
+// every screen is laid out for 128×64 landscape.
+//
+// Angles are computed from a low-pass filtered gravity vector rather than
+// filtering the angles themselves, so there is no ±180° wrap to worry about
+// and single-sample jolts don't register as a wrist raise.
+
+use crate::config::*;
+use crate::events::SensorData;
+use crate::ticks;
+
+/// Pitch and roll in degrees from a single accelerometer reading.
+///
+/// Roll uses the stabilised form `atan2(ay, sign(az)·√(az² + μ·ax²))`, which
+/// stays well-defined when the watch is held vertically (ay ≈ az ≈ 0) instead
+/// of swinging wildly between ±180°.
+pub fn pitch_roll(data: &SensorData) -> (f32, f32) {
+    const MU: f32 = 0.01;
+
+    let pitch = (-data.ax).atan2((data.ay * data.ay + data.az * data.az).sqrt());
+    let sign_z = if data.az >= 0.0 { 1.0 } else { -1.0 };
+    let roll = data.ay.atan2(sign_z * (data.az * data.az + MU * data.ax * data.ax).sqrt());
+
+    (pitch.to_degrees(), roll.to_degrees())
+}
+
+/// Detects the wrist being raised to look at the screen.
+pub struct WristRaiseDetector {
+    gravity: SensorData,
+    primed: bool,
+    /// `timestamp_ms` of the last sample with the roll at the low level;
+    /// `None` until it has been there (again) since the last raise.
+    low_at: Option<u32>,
+    /// `timestamp_ms` at which the wrist reached the raised angle, while
+    /// waiting for it to settle.
+    raised_at: Option<u32>,
+}
+
+impl WristRaiseDetector {
+    pub fn new() -> Self {
+        Self {
+            gravity: SensorData::default(),
+            primed: false,
+            low_at: None,
+            raised_at: None,
+        }
+    }
+
+    /// Smoothed `(pitch, roll)` in degrees.
+    pub fn angles(&self) -> (f32, f32) {
+        pitch_roll(&self.gravity)
+    }
+
+    /// Feed one IMU sample.  Returns `true` once per raise gesture (see the
+    /// module comment); the roll must fall back to the low level before the
+    /// next one can fire.
+    pub fn update(&mut self, data: &SensorData) -> bool {
+        if self.primed {
+            let a = ORIENTATION_SMOOTHING_ALPHA;
+            self.gravity.ax += a * (data.ax - self.gravity.ax);
+            self.gravity.ay += a * (data.ay - self.gravity.ay);
+            self.gravity.az += a * (data.az - self.gravity.az);
+        } else {
+            self.gravity = *data;
+            self.primed = true;
+        }
+
+        let (pitch, roll) = self.angles();
+
+        // Near vertical the roll estimate carries little information — hold state.
+        if pitch.abs() > ORIENTATION_MAX_PITCH_DEG {
+            return false;
+        }
+
+        let now = data.timestamp_ms;
+        if roll < WRIST_RAISE_ROLL_DEG - WRIST_RAISE_HYSTERESIS_DEG {
+            self.low_at = Some(now);
+            self.raised_at = None;
+            return false;
+        }
+
+        if let Some(raised_at) = self.raised_at {
+            let magnitude = (data.ax * data.ax + data.ay * data.ay + data.az * data.az).sqrt();
+            if roll <= WRIST_RAISE_ROLL_DEG || (magnitude - 1.0).abs() > WRIST_RAISE_STILL_G {
+                // Swung through rather than held: wait for the next lowering.
+                self.raised_at = None;
+                self.low_at = None;
+            } else if ticks::elapsed_ms(now, raised_at) >= WRIST_RAISE_SETTLE_MS {
+                self.raised_at = None;
+                self.low_at = None;
+                return true;
+            }
+        } else if roll > WRIST_RAISE_ROLL_DEG {
+            match self.low_at {
+                Some(low_at) if ticks::elapsed_ms(now, low_at) <= WRIST_RAISE_MAX_DURATION_MS => {
+                    self.raised_at = Some(now);
+                }
+                // Too slow to be a raise, or no lowering since the last one.
+                _ => self.low_at = None,
+            }
+        }
+        false
+    }
+}
+
+/// Detects the watch being worn upside down (see the module comment).
+pub struct ScreenOrientationDetector {
+    /// Low-pass filtered gravity along the panel's vertical, in g.
+    gravity: f32,
+    primed: bool,
+    flipped: bool,
+    /// Orientation the gravity currently points to and since when
+    /// (`timestamp_ms`), while it differs from `flipped`.
+    pending: Option<(bool, u32)>,
+}
+
+impl ScreenOrientationDetector {
+    pub fn new() -> Self {
+        Self {
+            gravity: 0.0,
+            primed: false,
+            flipped: false,
+            pending: None,
+        }
+    }
+
+    /// Feed one IMU sample.  Returns the new orientation (`true` = upside
+    /// down) once a change has held for [`ORIENTATION_DEBOUNCE_MS`].
+    pub fn update(&mut self, data: &SensorData) -> Option<bool> {
+        let up = ORIENTATION_UP_SIGN * data.ax;
+        if self.primed {
+            self.gravity += ORIENTATION_SMOOTHING_ALPHA * (up - self.gravity);
+        } else {
+            self.gravity = up;
+            self.primed = true;
+        }
+
+        let target = if self.gravity > ORIENTATION_FLIP_G {
+            false
+        } else if self.gravity < -ORIENTATION_FLIP_G {
+            true
+        } else {
+            self.flipped
+        };
+
+        if target == self.flipped {
+            self.pending = None;
+            return None;
+        }
+        match self.pending {
+            Some((pending, since)) if pending == target => {
+                if ticks::elapsed_ms(data.timestamp_ms, since) >= ORIENTATION_DEBOUNCE_MS {
+                    self.flipped = target;
+                    self.pending = None;
+                    return Some(target);
+                }
+            }
+            _ => self.pending = Some((target, data.timestamp_ms)),
+        }
+        None
+    }
+}
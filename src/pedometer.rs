@@ -0,0 +1,67 @@
+// PlastiWatch V2 — Step Counter
+//
+// Counts steps from the accelerometer magnitude |a| = √(ax² + ay² + az²),
+// which is independent of how the watch is oriented on the wrist.
+//
+// The magnitude is low-pass filtered, then each step is a peak that rises
+// above `STEP_THRESHOLD_HIGH_G`.  The detector only re-arms once the signal
+// falls back below `STEP_THRESHOLD_LOW_G` (hysteresis), and peaks closer than
+// `STEP_REFRACTORY_MS` to the previous step are ignored, so walking counts
+// reliably while idle tremor (which never leaves the band around 1 g) does not.
+//
+// The count carries over deep sleep (restored from NVS by `main`).
+
+use crate::config::*;
+use crate::events::SensorData;
+use crate::ticks;
+
+pub struct StepCounter {
+    filtered: f32,
+    primed: bool,
+    armed: bool,
+    last_step_ms: u32,
+    steps: u32,
+}
+
+impl StepCounter {
+    /// Start counting from `steps` (e.g. the total saved before sleep).
+    pub fn new(steps: u32) -> Self {
+        Self {
+            filtered: 1.0,
+            primed: false,
+            armed: false,
+            last_step_ms: 0,
+            steps,
+        }
+    }
+
+    /// Steps counted so far.
+    pub fn steps(&self) -> u32 {
+        self.steps
+    }
+
+    /// Feed one IMU sample.  Returns `true` when it completes a new step.
+    pub fn update(&mut self, data: &SensorData) -> bool {
+        let magnitude = (data.ax * data.ax + data.ay * data.ay + data.az * data.az).sqrt();
+
+        if self.primed {
+            self.filtered += PEDOMETER_SMOOTHING_ALPHA * (magnitude - self.filtered);
+        } else {
+            self.filtered = magnitude;
+            self.primed = true;
+        }
+
+        if self.filtered < STEP_THRESHOLD_LOW_G {
+            self.armed = true;
+        } else if self.armed
+            && self.filtered > STEP_THRESHOLD_HIGH_G
+            && ticks::elapsed_ms(data.timestamp_ms, self.last_step_ms) >= STEP_REFRACTORY_MS
+        {
+            self.armed = false;
+            self.last_step_ms = data.timestamp_ms;
+            self.steps += 1;
+            return true;
+        }
+        false
+    }
+}
@@ -0,0 +1,76 @@
+// PlastiWatch V2 — Reset Reason & Uptime
+//
+// Why the chip last started, from `esp_reset_reason()`.  A watchdog, panic or
+// brownout reset looks exactly like a normal start on the OLED, so the reason
+// is logged at boot and reported with the uptime by the serial `dump`
+// command.  Since every deep-sleep wake is a reset too, this is also how a
+// wake is told apart from a cold power-on: `main` skips the boot hold after a
+// button or motion (GPIO) wake, because the wearer has already asked for the
+// watch.  A timer wake still needs the hold, so an unattended periodic wake
+// goes straight back to sleep.
+
+use esp_idf_sys::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResetReason {
+    PowerOn,
+    DeepSleepWake,
+    /// Task, interrupt or RTC watchdog.
+    Watchdog,
+    Brownout,
+    Panic,
+    /// `esp_restart()`, e.g. after an unrecoverable I2C bus.
+    Software,
+    /// Reset pin.
+    External,
+    Unknown,
+}
+
+impl ResetReason {
+    #[allow(non_upper_case_globals)]
+    pub fn from_raw(raw: esp_reset_reason_t) -> Self {
+        match raw {
+            esp_reset_reason_t_ESP_RST_POWERON => ResetReason::PowerOn,
+            esp_reset_reason_t_ESP_RST_DEEPSLEEP => ResetReason::DeepSleepWake,
+            esp_reset_reason_t_ESP_RST_TASK_WDT
+            | esp_reset_reason_t_ESP_RST_INT_WDT
+            | esp_reset_reason_t_ESP_RST_WDT => ResetReason::Watchdog,
+            esp_reset_reason_t_ESP_RST_BROWNOUT => ResetReason::Brownout,
+            esp_reset_reason_t_ESP_RST_PANIC => ResetReason::Panic,
+            esp_reset_reason_t_ESP_RST_SW => ResetReason::Software,
+            esp_reset_reason_t_ESP_RST_EXT => ResetReason::External,
+            _ => ResetReason::Unknown,
+        }
+    }
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            ResetReason::PowerOn => "power-on",
+            ResetReason::DeepSleepWake => "deep-sleep wake",
+            ResetReason::Watchdog => "watchdog",
+            ResetReason::Brownout => "brownout",
+            ResetReason::Panic => "panic",
+            ResetReason::Software => "software restart",
+            ResetReason::External => "reset pin",
+            ResetReason::Unknown => "unknown",
+        }
+    }
+}
+
+/// Why the chip last reset.
+pub fn reason() -> ResetReason {
+    ResetReason::from_raw(unsafe { esp_reset_reason() })
+}
+
+/// Whether this boot is a deep-sleep wake by a GPIO source (button or
+/// motion interrupt).
+pub fn woken_by_gpio() -> bool {
+    reason() == ResetReason::DeepSleepWake
+        && unsafe { esp_sleep_get_wakeup_cause() } == esp_sleep_source_t_ESP_SLEEP_WAKEUP_GPIO
+}
+
+/// `now_ms()` as `H:MM:SS`.
+pub fn uptime() -> String {
+    let s = crate::ticks::uptime_s();
+    format!("{}:{:02}:{:02}", s / 3600, s / 60 % 60, s % 60)
+}
@@ -0,0 +1,121 @@
+// PlastiWatch V2 — Deep Sleep
+//
+// The one place deep sleep is entered and its wake sources are configured.
+// `main` (boot trigger not met) and the power task (long-press, inactivity,
+// console `sleep`) both call `enter_deep_sleep` with a `SleepConfig`:
+//
+//   button   `PIN_BUTTON` pulled low — always wanted, or the watch can only
+//            be woken by a reset
+//   motion   MPU6050 INT on `PIN_IMU_INT` driven high; only meaningful once
+//            the UI task has armed the interrupt (`MOTION_WAKE_ENABLED`)
+//   timer    wake after `timer_ms` (`SLEEP_TIMER_WAKE_MS`), e.g. to take a
+//            battery reading or resync the clock
+//
+// On the ESP32-C3 these are all compatible with each other.  Deep-sleep GPIO
+// wake is only available on the RTC-domain pins GPIO0–5 and is level
+// triggered, but the level is set per pin, so the active-low button and the
+// active-high IMU interrupt can be enabled together.  The classic ESP32's
+// ext0/ext1 and touch wake sources don't exist on the C3, and UART wake only
+// works from light sleep.
+//
+// Every wake is a reset: execution restarts in `main`.
+//
+// Turning a `SleepConfig` into register calls is split in two: `wake_sources`
+// is a pure function listing what to enable (so it can be checked without
+// hardware), and `enter_deep_sleep` just applies that list.
+
+use crate::config::*;
+
+/// Which wake sources to enable before sleeping.  Built up from
+/// [`SleepConfig::new`] (nothing enabled) or [`SleepConfig::from_config`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SleepConfig {
+    button: bool,
+    motion: bool,
+    timer_ms: Option<u64>,
+}
+
+/// One wake-up registration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WakeSource {
+    /// Deep-sleep GPIO wake when the pin reads low.
+    GpioLow(i32),
+    /// Deep-sleep GPIO wake when the pin reads high.
+    GpioHigh(i32),
+    /// Timer wake after this many microseconds.
+    TimerUs(u64),
+}
+
+impl SleepConfig {
+    /// No wake source at all (only a reset would wake the chip).
+    pub const fn new() -> Self {
+        Self {
+            button: false,
+            motion: false,
+            timer_ms: None,
+        }
+    }
+
+    /// Every source `config.rs` enables.
+    pub const fn from_config() -> Self {
+        Self::new().button(true).motion(MOTION_WAKE_ENABLED).timer(SLEEP_TIMER_WAKE_MS)
+    }
+
+    pub const fn button(mut self, enabled: bool) -> Self {
+        self.button = enabled;
+        self
+    }
+
+    pub const fn motion(mut self, enabled: bool) -> Self {
+        self.motion = enabled;
+        self
+    }
+
+    /// Wake after `ms`, or `None` for no timer.
+    pub const fn timer(mut self, ms: Option<u64>) -> Self {
+        self.timer_ms = ms;
+        self
+    }
+
+    /// The registrations `enter_deep_sleep` makes for this config, in order.
+    /// Pure: touches no hardware.
+    pub fn wake_sources(&self) -> Vec<WakeSource> {
+        let mut sources = Vec::with_capacity(3);
+        if self.button {
+            sources.push(WakeSource::GpioLow(PIN_BUTTON));
+        }
+        if self.motion {
+            sources.push(WakeSource::GpioHigh(PIN_IMU_INT));
+        }
+        if let Some(ms) = self.timer_ms {
+            sources.push(WakeSource::TimerUs(ms.saturating_mul(1000)));
+        }
+        sources
+    }
+}
+
+/// Enable the wake sources in `config` and enter deep sleep.  Does not return.
+pub fn enter_deep_sleep(config: SleepConfig) -> ! {
+    use esp_idf_sys::{
+        esp_deepsleep_gpio_wake_up_mode_t_ESP_GPIO_WAKEUP_GPIO_HIGH as HIGH,
+        esp_deepsleep_gpio_wake_up_mode_t_ESP_GPIO_WAKEUP_GPIO_LOW as LOW,
+    };
+
+    for source in config.wake_sources() {
+        log::info!("Deep sleep: wake on {:?}", source);
+        unsafe {
+            match source {
+                WakeSource::GpioLow(pin) => {
+                    esp_idf_sys::esp_deep_sleep_enable_gpio_wakeup(1u64 << pin, LOW);
+                }
+                WakeSource::GpioHigh(pin) => {
+                    esp_idf_sys::esp_deep_sleep_enable_gpio_wakeup(1u64 << pin, HIGH);
+                }
+                WakeSource::TimerUs(us) => {
+                    esp_idf_sys::esp_sleep_enable_timer_wakeup(us);
+                }
+            }
+        }
+    }
+    unsafe { esp_idf_sys::esp_deep_sleep_start() }
+}
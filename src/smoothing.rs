@@ -0,0 +1,123 @@
+// PlastiWatch V2 — Classification Smoothing
+//
+// Single-window classification is jittery: one stray "wave" window between
+// "idle" windows makes the activity screen flicker.  `ActivitySmoother` keeps
+// the last `EI_SMOOTHING_WINDOW` results and only reports an activity once it
+// is stable according to `EI_VOTING_POLICY`.
+//
+// The votes themselves are `decision::majority` / `decision::weighted`; this
+// keeps the ring of recent windows.
+//
+// Falls ("snake") can bypass smoothing (`EI_FALL_BYPASS_SMOOTHING`) so a
+// safety alert is never delayed by the vote.
+//
+// `ActivityTransitions` sits after the smoother: a steady activity is decided
+// again every window, but only a change is worth reporting, and only once the
+// new activity has been decided `EI_SWITCH_AFTER_WINDOWS` times in a row.
+
+use crate::config::*;
+use crate::decision;
+use crate::ei::{self, ClassifierResult};
+use crate::events::ActivityClass;
+
+/// How the last K windows are combined into one decision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VotingPolicy {
+    /// The same class must win more than half of the last K windows.
+    Majority,
+    /// Confidences averaged over the last K windows (recent windows weigh
+    /// more) must clear the class's threshold (`ei::threshold`).
+    WeightedAverage,
+}
+
+pub struct ActivitySmoother {
+    /// Ring of recent results; `None` = window below threshold.
+    history: [Option<ClassifierResult>; EI_SMOOTHING_WINDOW],
+    next: usize,
+}
+
+impl ActivitySmoother {
+    pub fn new() -> Self {
+        Self {
+            history: [None; EI_SMOOTHING_WINDOW],
+            next: 0,
+        }
+    }
+
+    /// Record the outcome of one inference window.  Returns the activity to
+    /// report to the UI, or `None` while the history is still undecided.
+    pub fn push(&mut self, result: Option<ClassifierResult>) -> Option<ActivityClass> {
+        self.history[self.next] = result;
+        self.next = (self.next + 1) % EI_SMOOTHING_WINDOW;
+
+        if let Some(r) = result {
+            if EI_FALL_BYPASS_SMOOTHING && r.activity == ActivityClass::Snake {
+                return Some(r.activity);
+            }
+        }
+
+        match EI_VOTING_POLICY {
+            VotingPolicy::Majority => self.majority(),
+            VotingPolicy::WeightedAverage => self.weighted_average(),
+        }
+    }
+
+    fn majority(&self) -> Option<ActivityClass> {
+        let votes = self.history.map(|r| r.map(|r| r.activity.index()));
+        decision::majority(&votes).map(ActivityClass::from_index)
+    }
+
+    fn weighted_average(&self) -> Option<ActivityClass> {
+        let oldest_first: [_; EI_SMOOTHING_WINDOW] = core::array::from_fn(|age| {
+            let slot = (self.next + age) % EI_SMOOTHING_WINDOW;
+            self.history[slot].map(|r| (r.activity.index(), r.confidence))
+        });
+        decision::weighted(&oldest_first, &ei::thresholds()).map(ActivityClass::from_index)
+    }
+}
+
+/// Filters the smoother's per-window decisions down to activity changes.
+pub struct ActivityTransitions {
+    reported: Option<ActivityClass>,
+    /// Activity decided in the latest windows and for how many in a row.
+    candidate: ActivityClass,
+    streak: u32,
+}
+
+impl ActivityTransitions {
+    pub fn new() -> Self {
+        Self {
+            reported: None,
+            candidate: ActivityClass::Unknown,
+            streak: 0,
+        }
+    }
+
+    /// Feed one smoothed decision.  Returns it when it should be reported:
+    /// it differs from the last reported activity and has now persisted for
+    /// `EI_SWITCH_AFTER_WINDOWS` windows.
+    pub fn update(&mut self, activity: ActivityClass) -> Option<ActivityClass> {
+        if self.reported == Some(activity) {
+            self.streak = 0;
+            return None;
+        }
+        if activity == self.candidate {
+            self.streak += 1;
+        } else {
+            self.candidate = activity;
+            self.streak = 1;
+        }
+        if self.streak >= EI_SWITCH_AFTER_WINDOWS {
+            self.set_reported(activity);
+            Some(activity)
+        } else {
+            None
+        }
+    }
+
+    /// Note an activity reported outside `update` (a fall, "unknown").
+    pub fn set_reported(&mut self, activity: ActivityClass) {
+        self.reported = Some(activity);
+        self.streak = 0;
+    }
+}
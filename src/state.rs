@@ -0,0 +1,111 @@
+// PlastiWatch V2 — Shared System State
+//
+// The device's "current truth" in one place: each task keeps its own fields
+// of a `SystemState` up to date, and readers (serial console, status
+// screens, telemetry) lock it once to get a consistent snapshot instead of
+// piecing one together from events.
+//
+//   AI task      activity, confidence, steps, inference_us, dwell, worn,
+//                last_activity_ms
+//   power task   battery_pct, battery_v, charging, last_activity_ms
+//   sensor task  accel_g, temp_c
+//   UI task      last_activity_ms (button presses, wrist raise), dwell reset
+//
+// `UiEvent`s still carry changes to the UI; the state is what you read when
+// you need the value now rather than a notification.
+//
+// Consumers outside the tasks above (BLE, serial console, diagnostics) read
+// what the watch is reporting through `status`, which copies the headline
+// figures under a single lock.  That makes the copy consistent with itself at
+// one instant — no producer can update a field halfway through — but not a
+// coherent "moment" of the device: each producer writes its own fields on its
+// own schedule, so `steps` may be a window newer than `activity`, and the
+// battery figures can be up to `BATTERY_CHECK_INTERVAL_MS` old.  Locking
+// field by field (as a series of `state.lock().unwrap().x` reads would) adds
+// the risk of a producer landing between two reads; don't.
+
+use std::sync::{Arc, Mutex};
+
+use crate::dwell::DwellTally;
+use crate::events::ActivityClass;
+
+#[derive(Debug, Clone, Copy)]
+pub struct SystemState {
+    /// Activity last reported by the classifier.
+    pub activity: ActivityClass,
+    /// Confidence of the latest window in `activity` (0.0–1.0).
+    pub confidence: f32,
+    /// Battery level (%), once the first reading is taken.
+    pub battery_pct: Option<f32>,
+    /// Smoothed battery voltage (V), once the first reading is taken.
+    pub battery_v: Option<f32>,
+    pub steps: u32,
+    pub charging: bool,
+    /// Latest calibrated acceleration (g), once the first sample is read.
+    pub accel_g: Option<[f32; 3]>,
+    /// MPU6050 die temperature (°C), once the first sample is read.
+    pub temp_c: Option<f32>,
+    /// Duration of the latest inference (µs), once one has run.
+    pub inference_us: Option<u32>,
+    /// Time spent in each activity today (or since boot).
+    pub dwell: DwellTally,
+    /// `now_ms()` of the last user interaction or detected movement; the
+    /// screen dims, switches off and the power task sleeps after
+    /// `DIM_TIMEOUT_MS`, `SCREEN_OFF_TIMEOUT_MS` and `DEEP_SLEEP_TIMEOUT_MS`
+    /// without one.
+    pub last_activity_ms: u32,
+    /// On a wrist per the wear detector (`WEAR_DETECTION_ENABLED`); holds
+    /// off deep sleep but not the screen stages.
+    pub worn: bool,
+}
+
+pub type SharedState = Arc<Mutex<SystemState>>;
+
+/// What the watch is currently reporting, for integrators.
+#[derive(Debug, Clone, Copy)]
+pub struct Status {
+    pub activity: ActivityClass,
+    /// Confidence of the latest window in `activity` (0.0–1.0).
+    pub confidence: f32,
+    /// Battery level (%) and voltage (V), once the first reading is taken.
+    pub battery_pct: Option<f32>,
+    pub battery_v: Option<f32>,
+    pub charging: bool,
+    pub steps: u32,
+}
+
+/// Snapshot of the headline state, taken under one lock (see the header for
+/// what that does and doesn't guarantee).  Safe to call from any thread.
+pub fn status(state: &SharedState) -> Status {
+    state.lock().unwrap().status()
+}
+
+impl SystemState {
+    pub fn new(steps: u32) -> Self {
+        Self {
+            activity: ActivityClass::default(),
+            confidence: 0.0,
+            battery_pct: None,
+            battery_v: None,
+            steps,
+            charging: false,
+            accel_g: None,
+            temp_c: None,
+            inference_us: None,
+            dwell: DwellTally::new(ActivityClass::default(), crate::now_ms()),
+            last_activity_ms: crate::now_ms(),
+            worn: false,
+        }
+    }
+
+    pub fn status(&self) -> Status {
+        Status {
+            activity: self.activity,
+            confidence: self.confidence,
+            battery_pct: self.battery_pct,
+            battery_v: self.battery_v,
+            charging: self.charging,
+            steps: self.steps,
+        }
+    }
+}
@@ -1,65 +1,384 @@
 // PlastiWatch V2 — AI Inference Task
 //
-// Buffers 125 accelerometer samples (2-second window at 62.5 Hz), then runs
-// the Edge Impulse classifier.  When confidence exceeds the threshold, the
-// detected activity is forwarded to the UI task.
+// Buffers `window_samples` IMU samples (`EI_WINDOW_SAMPLES` from main: by
+// default the model's 125, a 2-second window at 62.5 Hz), then runs the Edge
+// Impulse classifier.  A length the back-end can't take (`ei::check_window`)
+// or shorter than the hop is logged and replaced by the model's own.  Windows
+// overlap: after each inference the buffer slides forward by
+// `EI_WINDOW_HOP_SAMPLES` (~500 ms), so a new classification is produced
+// every hop rather than every 2 seconds.  Results pass through
+// `ActivitySmoother` (majority vote / weighted average over the last few
+// windows), and `UiEvent::UpdateActivity` is only sent when the smoothed
+// activity changes and holds for `EI_SWITCH_AFTER_WINDOWS` windows
+// (`ActivityTransitions`), so a steady activity doesn't redraw the UI every
+// hop.  With `EI_NORMALIZE_FEATURES` a standardised copy of the window is
+// classified; the raw buffer is kept for sliding.
+//
+// With `EI_REMOVE_GRAVITY` the accelerometer values go into the window with
+// their running mean (`filter::GravityFilter`) subtracted.  The estimate
+// carries over as the window slides and restarts with every fresh window.
+//
+// Each sample carries the time it was read.  If the first and last samples of
+// a window are not as far apart as the window is long (IMU read errors, a
+// stalled sensor task) the window no longer represents the motion the model
+// was trained on, so it is discarded and filled again from scratch.  Every
+// sample is also fed to the wrist-raise detector, the screen-orientation
+// detector (with `AUTO_ROTATE_ENABLED`), the step counter and (with
+// `WEAR_DETECTION_ENABLED`) the wear detector, which keeps the watch out of
+// deep sleep while it is on a wrist even if no activity clears its threshold.
+//
+// After `EI_IDLE_WINDOWS_BEFORE_SLOW` consecutive "idle" decisions the task
+// lowers the shared sample rate to save power.  The model only understands
+// 2-second windows at the full rate, so inference pauses while slowed; any
+// motion (|a| departing from 1 g by `EI_WAKE_MOTION_G`) restores the full
+// rate and the window refills from scratch.
+//
+// When no class clears its threshold for `EI_UNKNOWN_AFTER_WINDOWS` windows
+// in a row, `ActivityClass::Unknown` is reported so the UI stops showing a
+// stale activity.
+//
+// Each inference's latency is published in `SystemState::inference_us`; a
+// warning is logged when it exceeds `EI_INFERENCE_WARN_PCT` of the hop
+// period, since past 100 % samples back up in the sensor channel.
+//
+// Samples are expected at least every `SENSOR_IDLE_SAMPLE_INTERVAL_MS`.  If
+// none arrives for `SENSOR_LOST_TIMEOUT_MS` — the sensor task is stuck or has
+// given up and exited — `UiEvent::SensorLost` puts a marker on the screen
+// instead of leaving a frozen-looking activity, and `SensorRestored` clears
+// it.  After `SENSOR_LOST_RESTART_MS` without samples the chip restarts,
+// which re-initialises the bus, the IMU and the sensor task — unless the
+// sensor task reports the IMU disconnected and is probing for it.
+//
+// While the window fills, `UiEvent::WindowProgress` reports how full it is
+// every `EI_PROGRESS_EVERY_SAMPLES` samples (not every sample, which would
+// flood the channel), so the UI can show that motion is being analysed before
+// the first result lands; 0.0 is sent when the rate drops and inference
+// pauses.
+//
+// Every reported activity also feeds the per-class dwell-time tally in
+// `SystemState::dwell`.
+//
+// Every change of reported activity is also appended to the flash-backed
+// `history` log.
+//
+// A fall is not reported as an ordinary activity: entering "snake" sends
+// `UiEvent::FallDetected` once, and the UI raises an alert.
 
-use std::sync::atomic::{AtomicU32, Ordering};
-use std::sync::mpsc::{Receiver, Sender};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
 
+use crate::broadcast::Broadcast;
+use crate::clock;
 use crate::config::*;
 use crate::ei;
-use crate::events::{SensorData, UiEvent};
+use crate::events::{ActivityClass, SensorData, UiEvent};
+use crate::filter::GravityFilter;
+use crate::history;
+use crate::orientation::{ScreenOrientationDetector, WristRaiseDetector};
+use crate::pedometer::StepCounter;
+use crate::smoothing::{ActivitySmoother, ActivityTransitions};
+use crate::state::{SharedState, SystemState};
+use crate::tasks;
+use crate::ticks::{self, Clock};
+use crate::watchdog;
+use crate::wear::WearDetector;
 
-pub fn ai_task(
+pub fn ai_task<C: Clock>(
     sensor_rx: Receiver<SensorData>,
-    ui_tx: Sender<UiEvent>,
-    last_activity_ms: Arc<AtomicU32>,
+    ui_tx: Broadcast<UiEvent>,
+    state: SharedState,
+    sample_interval_ms: Arc<AtomicU64>,
+    initial_steps: u32,
+    window_samples: usize,
+    clock: C,
 ) {
     log::info!("AI task started");
+    let watchdog = watchdog::subscribe("ai");
 
-    let mut features = [0.0f32; EI_DSP_INPUT_FRAME_SIZE];
+    let window_samples = match ei::check_window(window_samples * EI_RAW_SAMPLES_PER_FRAME) {
+        Ok(()) if window_samples >= EI_WINDOW_HOP_SAMPLES.max(2) => window_samples,
+        Ok(()) => {
+            log::error!(
+                "Window of {} samples is shorter than the hop — using {}",
+                window_samples,
+                EI_RAW_SAMPLE_COUNT
+            );
+            EI_RAW_SAMPLE_COUNT
+        }
+        Err(e) => {
+            log::error!("{} — using {} samples", e, EI_RAW_SAMPLE_COUNT);
+            EI_RAW_SAMPLE_COUNT
+        }
+    };
+    let window_len = window_samples * EI_RAW_SAMPLES_PER_FRAME;
+    let window_span_ms = (window_samples as u32 - 1) * SENSOR_SAMPLE_INTERVAL_MS as u32;
+    log::info!("Classifying {}-sample windows (~{} ms)", window_samples, window_span_ms);
+
+    let mut features = vec![0.0f32; window_len];
+    let mut normalized = vec![0.0f32; window_len];
     let mut feature_ix: usize = 0;
+    let mut timestamps = vec![0u32; window_samples];
+    let hop_len = EI_WINDOW_HOP_SAMPLES * EI_RAW_SAMPLES_PER_FRAME;
+    let mut wrist = WristRaiseDetector::new();
+    let mut orientation = ScreenOrientationDetector::new();
+    let mut smoother = ActivitySmoother::new();
+    let mut transitions = ActivityTransitions::new();
+    let mut pedometer = StepCounter::new(initial_steps);
+    let mut wear = WearDetector::new();
+    let mut gravity = GravityFilter::new();
+    let mut in_fall = false;
+    let mut idle_windows: u32 = 0;
+    let mut unsure_windows: u32 = 0;
+    let mut low_rate = false;
+    let sensor_timeout = Duration::from_millis(SENSOR_LOST_TIMEOUT_MS);
+    let mut last_sample = clock.now_ms();
+    let mut sensor_lost = false;
 
     loop {
-        // Block until a sensor sample arrives.
-        let data = match sensor_rx.recv() {
+        watchdog.feed();
+        // Wait for the next sensor sample; a long silence means the sensor
+        // task is stuck or gone.
+        let data = match sensor_rx.recv_timeout(sensor_timeout) {
             Ok(d) => d,
-            Err(_) => {
-                log::warn!("Sensor channel closed — exiting AI task");
-                return;
+            Err(e) => {
+                if e == RecvTimeoutError::Disconnected {
+                    // Returns immediately from now on: pace the loop.
+                    thread::sleep(sensor_timeout);
+                }
+                let silent_ms = clock.ms_since(last_sample) as u64;
+                if !sensor_lost {
+                    log::error!("No IMU sample for {} ms — sensor lost", silent_ms);
+                    sensor_lost = true;
+                    let _ = ui_tx.send(UiEvent::SensorLost);
+                }
+                // An unplugged IMU is the sensor task's to wait out.
+                if silent_ms >= SENSOR_LOST_RESTART_MS && !tasks::sensor::health().disconnected {
+                    log::error!("Sensor lost for {} s — restarting", silent_ms / 1000);
+                    unsafe { esp_idf_sys::esp_restart() }
+                }
+                continue;
             }
         };
+        last_sample = clock.now_ms();
+        if std::mem::take(&mut sensor_lost) {
+            log::info!("IMU samples resumed");
+            let _ = ui_tx.send(UiEvent::SensorRestored);
+        }
+
+        if wrist.update(&data) {
+            let (pitch, roll) = wrist.angles();
+            log::debug!("Wrist raised (pitch {:.0}°, roll {:.0}°)", pitch, roll);
+            let _ = ui_tx.send(UiEvent::WristRaised);
+        }
+
+        if AUTO_ROTATE_ENABLED {
+            if let Some(flipped) = orientation.update(&data) {
+                log::info!("Screen orientation: {}", if flipped { "flipped" } else { "normal" });
+                let _ = ui_tx.send(UiEvent::OrientationChanged(flipped));
+            }
+        }
+
+        if pedometer.update(&data) {
+            state.lock().unwrap().steps = pedometer.steps();
+            let _ = ui_tx.send(UiEvent::UpdateSteps(pedometer.steps()));
+        }
+
+        if WEAR_DETECTION_ENABLED {
+            if let Some(worn) = wear.update(&data) {
+                // Worn but sitting still keeps the watch out of deep sleep.
+                state.lock().unwrap().worn = worn;
+            }
+        }
+
+        // Recording training data: keep the full sample rate and don't classify.
+        #[cfg(feature = "capture")]
+        if crate::capture::is_active() {
+            if low_rate {
+                sample_interval_ms.store(SENSOR_SAMPLE_INTERVAL_MS, Ordering::Relaxed);
+                low_rate = false;
+            }
+            idle_windows = 0;
+            feature_ix = 0;
+            continue;
+        }
 
-        // Accumulate 3-axis accelerometer values into the feature buffer.
-        if feature_ix + EI_RAW_SAMPLES_PER_FRAME > EI_DSP_INPUT_FRAME_SIZE {
+        if low_rate {
+            let magnitude = (data.ax * data.ax + data.ay * data.ay + data.az * data.az).sqrt();
+            if (magnitude - 1.0).abs() > EI_WAKE_MOTION_G {
+                log::info!("Motion — restoring full sample rate");
+                sample_interval_ms.store(SENSOR_SAMPLE_INTERVAL_MS, Ordering::Relaxed);
+                low_rate = false;
+                idle_windows = 0;
+                feature_ix = 0;
+            }
+            // Windows at the slow rate mean nothing to the model.
+            continue;
+        }
+
+        // Accumulate accelerometer (and optionally gyro) values into the feature buffer.
+        if feature_ix + EI_RAW_SAMPLES_PER_FRAME > window_len {
             // Safety guard — should never happen, but reset gracefully.
             feature_ix = 0;
         }
 
-        features[feature_ix] = data.ax;
-        features[feature_ix + 1] = data.ay;
-        features[feature_ix + 2] = data.az;
+        let accel = if EI_REMOVE_GRAVITY {
+            if feature_ix == 0 {
+                gravity.reset();
+            }
+            gravity.apply([data.ax, data.ay, data.az], SENSOR_SAMPLE_INTERVAL_MS)
+        } else {
+            [data.ax, data.ay, data.az]
+        };
+
+        timestamps[feature_ix / EI_RAW_SAMPLES_PER_FRAME] = data.timestamp_ms;
+        features[feature_ix..feature_ix + 3].copy_from_slice(&accel);
+        if EI_USE_GYRO {
+            features[feature_ix + 3] = data.gx;
+            features[feature_ix + 4] = data.gy;
+            features[feature_ix + 5] = data.gz;
+        }
         feature_ix += EI_RAW_SAMPLES_PER_FRAME;
 
-        // Once the buffer is full (125 samples), run inference.
-        if feature_ix >= EI_DSP_INPUT_FRAME_SIZE {
-            if let Some(result) = ei::classify(&features) {
+        if (feature_ix / EI_RAW_SAMPLES_PER_FRAME) % EI_PROGRESS_EVERY_SAMPLES == 0 {
+            let progress = feature_ix as f32 / window_len as f32;
+            let _ = ui_tx.send(UiEvent::WindowProgress(progress));
+        }
+
+        // Once the window is full, run inference.
+        if feature_ix >= window_len {
+            let span = ticks::elapsed_ms(timestamps[window_samples - 1], timestamps[0]);
+            if span.abs_diff(window_span_ms) > EI_WINDOW_SPAN_TOLERANCE_MS {
+                log::warn!(
+                    "Window spans {} ms (expected {} ms) — discarding",
+                    span,
+                    window_span_ms
+                );
+                feature_ix = 0;
+                continue;
+            }
+
+            let inference = if EI_NORMALIZE_FEATURES {
+                normalized.copy_from_slice(&features);
+                ei::normalize(&mut normalized);
+                ei::classify_full(&normalized)
+            } else {
+                ei::classify_full(&features)
+            };
+            if let Some(ei::Inference { timing, .. }) = inference {
+                log_timing(&timing);
+                state.lock().unwrap().inference_us = Some(timing.total_us);
+            }
+            let scores = inference.map(|i| i.scores);
+            let result = scores.as_ref().and_then(ei::select);
+            if let Some(result) = result {
                 log::info!(
                     "Activity: {:?} ({:.1}%)",
                     result.activity,
                     result.confidence * 100.0
                 );
 
-                // Update the activity timestamp (prevents inactivity sleep while moving).
-                last_activity_ms.store(crate::now_ms(), Ordering::Relaxed);
+                // Update the activity timestamp while moving (keeps the
+                // screen on and prevents inactivity sleep).
+                if result.activity != ActivityClass::Idle {
+                    state.lock().unwrap().last_activity_ms = clock.now_ms();
+                }
+            }
+
+            if let Some(mut ranked) = scores {
+                ranked.sort_by(|a, b| ei::compare_confidence(b, a));
+                log::debug!(
+                    "Runner-up: {:?} ({:.1}%)",
+                    ranked[1].activity,
+                    ranked[1].confidence * 100.0
+                );
+            }
+
+            if result.is_some() {
+                unsure_windows = 0;
+            } else {
+                unsure_windows += 1;
+                if unsure_windows == EI_UNKNOWN_AFTER_WINDOWS {
+                    in_fall = false;
+                    idle_windows = 0;
+                    transitions.set_reported(ActivityClass::Unknown);
+                    history::record(ActivityClass::Unknown, 0.0);
+                    set_activity(&state, clock.now_ms(), ActivityClass::Unknown, 0.0);
+                    let _ = ui_tx.send(UiEvent::UpdateActivity(ActivityClass::Unknown, 0.0));
+                }
+            }
 
-                let _ = ui_tx.send(UiEvent::UpdateActivity(result.activity));
+            match smoother.push(result) {
+                Some(ActivityClass::Snake) if !in_fall => {
+                    in_fall = true;
+                    let snake = ActivityClass::Snake;
+                    let confidence = scores.map_or(0.0, |s| s[snake.index()].confidence);
+                    transitions.set_reported(snake);
+                    history::record(snake, confidence);
+                    set_activity(&state, clock.now_ms(), snake, confidence);
+                    let _ = ui_tx.send(UiEvent::FallDetected);
+                }
+                Some(ActivityClass::Snake) => {}
+                Some(activity) => {
+                    in_fall = false;
+                    idle_windows = if activity == ActivityClass::Idle { idle_windows + 1 } else { 0 };
+                    if let Some(activity) = transitions.update(activity) {
+                        // How sure the latest window is of the new activity.
+                        let confidence = scores.map_or(0.0, |s| s[activity.index()].confidence);
+                        history::record(activity, confidence);
+                        set_activity(&state, clock.now_ms(), activity, confidence);
+                        let _ = ui_tx.send(UiEvent::UpdateActivity(activity, confidence));
+                    }
+                }
+                None => {}
             }
 
-            // Reset buffer for the next window.
-            feature_ix = 0;
+            if idle_windows >= EI_IDLE_WINDOWS_BEFORE_SLOW {
+                log::info!("Sustained idle — lowering sample rate");
+                sample_interval_ms.store(SENSOR_IDLE_SAMPLE_INTERVAL_MS, Ordering::Relaxed);
+                low_rate = true;
+                feature_ix = 0;
+                let _ = ui_tx.send(UiEvent::WindowProgress(0.0));
+                continue;
+            }
+
+            // Slide the window: drop the oldest hop, keep the rest in place.
+            features.copy_within(hop_len.., 0);
+            timestamps.copy_within(EI_WINDOW_HOP_SAMPLES.., 0);
+            feature_ix -= hop_len;
         }
     }
 }
+
+/// Log one inference's cost, warning when it nears the hop period.
+fn log_timing(timing: &ei::InferenceTiming) {
+    let hop_us = (EI_WINDOW_HOP_SAMPLES as u64 * SENSOR_SAMPLE_INTERVAL_MS * 1000) as u32;
+    if timing.total_us > hop_us / 100 * EI_INFERENCE_WARN_PCT {
+        log::warn!(
+            "Inference took {} µs ({}% of the {} µs hop)",
+            timing.total_us,
+            timing.total_us / (hop_us / 100),
+            hop_us
+        );
+    } else {
+        log::debug!(
+            "Inference {} µs (dsp {}, classifier {}, anomaly {})",
+            timing.total_us,
+            timing.dsp_us,
+            timing.classification_us,
+            timing.anomaly_us
+        );
+    }
+}
+
+fn set_activity(state: &Mutex<SystemState>, now: u32, activity: ActivityClass, confidence: f32) {
+    let mut state = state.lock().unwrap();
+    state.activity = activity;
+    state.confidence = confidence;
+    if DWELL_RESET_AT_MIDNIGHT {
+        state.dwell.roll_over(clock::local_day(), now);
+    }
+    state.dwell.switch(activity, now);
+}
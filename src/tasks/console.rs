@@ -0,0 +1,233 @@
+// PlastiWatch V2 — Serial Console Task
+//
+// Reads line-based commands from the USB/UART console (stdin) for field
+// tuning and diagnostics without reflashing.  Replies go to stdout; a
+// malformed line gets an "error: …" reply and is otherwise ignored.
+//
+// Commands:
+//   time            print the current time
+//   time HH:MM[:SS] set the clock
+//   brightness N    set the display brightness (0–255, saved across sleep)
+//   invert on|off   inverted (dark-on-light) rendering
+//   demo on|off     cycle the activity screen through every activity
+//   threshold [X]   print / set the confidence threshold for every class
+//   calibrate       re-measure IMU offsets (lay the watch flat, face up)
+//   status          machine-readable snapshot for external tools:
+//                   "activity=N confidence=C battery=P volts=V charging=0|1
+//                   steps=S" (N per `ActivityClass::index`, C 0.0–1.0,
+//                   "?" for a battery figure not yet read)
+//   dump            last activity, battery, steps, uptime and reset reason
+//   history         logged activity changes, oldest first
+//   dwell [reset]   time spent in each activity today / clear it
+//   sensor          IMU read-error and re-init counters, disconnection
+//   sleep           enter deep sleep as if the button were held
+//   capture on|off  stream CSV training data (feature = "capture")
+
+use std::io::{self, BufRead};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::broadcast::Broadcast;
+use crate::clock;
+use crate::config::*;
+use crate::dwell;
+use crate::ei;
+use crate::events::{ActivityClass, UiEvent};
+use crate::history;
+use crate::reset;
+use crate::state::{self, SharedState};
+use crate::tasks;
+
+/// What the console commands act on besides the UI channel.
+struct Context {
+    ui_tx: Broadcast<UiEvent>,
+    state: SharedState,
+    sleep_requested: Arc<AtomicBool>,
+}
+
+pub fn console_task(
+    ui_tx: Broadcast<UiEvent>,
+    state: SharedState,
+    sleep_requested: Arc<AtomicBool>,
+) {
+    log::info!("Console task started");
+
+    let ctx = Context {
+        ui_tx,
+        state,
+        sleep_requested,
+    };
+
+    let stdin = io::stdin();
+    let mut line = String::new();
+
+    loop {
+        // The ESP-IDF console may be non-blocking: keep partial input until
+        // the newline arrives.
+        match stdin.lock().read_line(&mut line) {
+            Ok(_) if line.ends_with('\n') => {
+                match execute(line.trim(), &ctx) {
+                    Ok(reply) if reply.is_empty() => {}
+                    Ok(reply) => println!("{}", reply),
+                    Err(e) => println!("error: {}", e),
+                }
+                line.clear();
+            }
+            _ => thread::sleep(Duration::from_millis(CONSOLE_POLL_INTERVAL_MS)),
+        }
+    }
+}
+
+/// Run one command line and return the reply.
+fn execute(line: &str, ctx: &Context) -> anyhow::Result<String> {
+    let mut words = line.split_whitespace();
+    let Some(command) = words.next() else {
+        return Ok(String::new());
+    };
+
+    match (command, words.next()) {
+        ("time", None) => Ok(match clock::now() {
+            Some(t) => format!("{:02}:{:02}:{:02}", t.hour, t.minute, t.second),
+            None => "time not set".into(),
+        }),
+        ("time", Some(arg)) => {
+            let mut parts = arg.split(':').map(str::parse::<u8>);
+            let (Some(Ok(hour)), Some(Ok(minute))) = (parts.next(), parts.next()) else {
+                anyhow::bail!("usage: time HH:MM[:SS]");
+            };
+            let second = match parts.next() {
+                Some(Ok(s)) => s,
+                Some(Err(_)) => anyhow::bail!("usage: time HH:MM[:SS]"),
+                None => 0,
+            };
+            clock::set_time(hour, minute, second)?;
+            Ok("ok".into())
+        }
+        ("brightness", Some(arg)) => {
+            let level: u8 = arg
+                .parse()
+                .map_err(|_| anyhow::anyhow!("usage: brightness 0-255"))?;
+            ctx.ui_tx.send(UiEvent::SetBrightness(level))?;
+            Ok("ok".into())
+        }
+        ("invert", Some(arg @ ("on" | "off"))) => {
+            ctx.ui_tx.send(UiEvent::SetInvert(arg == "on"))?;
+            Ok("ok".into())
+        }
+        ("demo", Some(arg @ ("on" | "off"))) => {
+            ctx.ui_tx.send(UiEvent::SetDemo(arg == "on"))?;
+            Ok("ok".into())
+        }
+        ("threshold", None) => Ok(format!("{:.2}", ei::threshold(0))),
+        ("threshold", Some(arg)) => {
+            let value: f32 = arg
+                .parse()
+                .map_err(|_| anyhow::anyhow!("usage: threshold 0.0-1.0"))?;
+            ei::set_threshold(value)?;
+            Ok("ok".into())
+        }
+        ("calibrate", None) => {
+            tasks::sensor::request_calibration();
+            Ok("calibrating — keep the watch flat and still".into())
+        }
+        ("status", None) => {
+            let status = state::status(&ctx.state);
+            let or_unknown = |value: Option<f32>, decimals: usize| match value {
+                Some(v) => format!("{:.*}", decimals, v),
+                None => "?".into(),
+            };
+            Ok(format!(
+                "activity={} confidence={:.2} battery={} volts={} charging={} steps={}",
+                status.activity.index(),
+                status.confidence,
+                or_unknown(status.battery_pct, 0),
+                or_unknown(status.battery_v, 2),
+                u8::from(status.charging),
+                status.steps
+            ))
+        }
+        ("dump", None) => {
+            let state = *ctx.state.lock().unwrap();
+            let battery = match state.battery_pct {
+                Some(pct) => format!("{:.0}%", pct),
+                None => "?".into(),
+            };
+            let temp = match state.temp_c {
+                Some(t) => format!("{:.1}C", t),
+                None => "?".into(),
+            };
+            let inference = match state.inference_us {
+                Some(us) => format!("{}us", us),
+                None => "?".into(),
+            };
+            Ok(format!(
+                "activity {} ({:.0}%) battery {}{} steps {} temp {} inference {} uptime {} reset {}",
+                state.activity.display_name(),
+                state.confidence * 100.0,
+                battery,
+                if state.charging { " charging" } else { "" },
+                state.steps,
+                temp,
+                inference,
+                reset::uptime(),
+                reset::reason().display_name()
+            ))
+        }
+        ("sleep", None) => {
+            ctx.sleep_requested.store(true, Ordering::SeqCst);
+            Ok("ok".into())
+        }
+        #[cfg(feature = "capture")]
+        ("capture", Some(arg @ ("on" | "off"))) => {
+            match (arg == "on", crate::capture::is_active()) {
+                (true, false) => crate::capture::start(),
+                (false, true) => crate::capture::stop(),
+                _ => {}
+            }
+            // No reply: it would land in the CSV stream.
+            Ok(String::new())
+        }
+        ("history", None) => {
+            let mut out = String::new();
+            for e in history::entries() {
+                let when = match e.unix {
+                    0 => format!("uptime {}s", e.uptime_s),
+                    unix => clock::format_local(unix as i64),
+                };
+                out += &format!("{} {} {}%\n", when, e.activity.display_name(), e.confidence);
+            }
+            out.pop();
+            Ok(if out.is_empty() { "history empty".into() } else { out })
+        }
+        ("dwell", None) => {
+            let totals = ctx.state.lock().unwrap().dwell.totals(crate::now_ms());
+            let parts: Vec<String> = totals
+                .iter()
+                .enumerate()
+                .map(|(index, &ms)| {
+                    let name = ActivityClass::from_index(index).display_name();
+                    format!("{} {}", name, dwell::format_duration(ms))
+                })
+                .collect();
+            Ok(parts.join(", "))
+        }
+        ("dwell", Some("reset")) => {
+            ctx.state.lock().unwrap().dwell.reset(crate::now_ms());
+            Ok("ok".into())
+        }
+        ("sensor", None) => {
+            let h = tasks::sensor::health();
+            Ok(format!(
+                "read errors {} (consecutive {}), re-inits {}, bus recoveries {}{}",
+                h.read_errors,
+                h.consecutive_errors,
+                h.reinits,
+                crate::drivers::bus::recoveries(),
+                if h.disconnected { ", IMU disconnected" } else { "" }
+            ))
+        }
+        _ => anyhow::bail!("unknown command '{}'", line),
+    }
+}
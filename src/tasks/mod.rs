@@ -2,3 +2,4 @@ pub mod sensor;
 pub mod ai;
 pub mod ui;
 pub mod power;
+pub mod console;
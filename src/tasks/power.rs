@@ -1,23 +1,55 @@
 // PlastiWatch V2 — Power Management Task
 //
 // Periodically reads battery voltage, sends updates to the UI, and handles
-// deep-sleep entry on long-press or inactivity timeout.
+// deep-sleep entry on long-press or inactivity timeout
+// (`DEEP_SLEEP_TIMEOUT_MS`).  While charging, or while worn, the inactivity
+// timeout is suspended; the UI task's dim and screen-off stages still apply.
+//
+// Sleep is never entered abruptly: `prepare_for_sleep` raises
+// `sleep_requested` and waits (bounded) for the UI task to stop the motor and
+// blank the OLED and signal `sleep_ready`, so no peripheral is left driven
+// across deep sleep.  The shared `PersistentState` is then written to NVS
+// (if it changed) so steps and settings survive the sleep.  The activity
+// history is flushed along with it, and every `HISTORY_FLUSH_INTERVAL_MS`
+// in between.
+//
+// Each check takes the median of `BATTERY_ADC_SAMPLES` ADC readings, sampled
+// only while the haptic motor is idle (its current draw dips the rail), and
+// smooths the result across checks with an exponential moving average.  The
+// percentage reported to the UI then goes through `battery::Gauge`, which
+// limits how fast it moves and keeps it from rising unless charging.
+//
+// Raw counts are converted to millivolts with the ESP-IDF curve-fitting ADC
+// calibration (per-chip eFuse characterisation), which corrects the C3's
+// nonlinear ADC.  If the scheme can't be created the naive linear
+// `raw / 4095 × 3.3 V` conversion is used instead.
 
-use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
-use std::sync::mpsc::Sender;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use crate::battery;
+use crate::broadcast::Broadcast;
 use crate::config::*;
+use crate::drivers::haptic;
 use crate::events::UiEvent;
+use crate::nvs::{PersistentState, Store};
+use crate::ticks;
+use crate::sleep::{self, SleepConfig};
+use crate::state::SharedState;
+use crate::watchdog;
 
 pub fn power_task(
-    ui_tx: Sender<UiEvent>,
+    ui_tx: Broadcast<UiEvent>,
     sleep_requested: Arc<AtomicBool>,
-    last_activity_ms: Arc<AtomicU32>,
+    sleep_ready: Arc<AtomicBool>,
+    state: SharedState,
+    mut store: Option<Store>,
+    persistent: Arc<Mutex<PersistentState>>,
 ) {
     log::info!("Power task started");
+    let watchdog = watchdog::subscribe("power");
 
     let check_interval = Duration::from_millis(BATTERY_CHECK_INTERVAL_MS);
 
@@ -45,30 +77,104 @@ pub fn power_task(
             log::error!("ADC channel config failed ({})", ret);
         }
 
+        let cali_cfg = esp_idf_sys::adc_cali_curve_fitting_config_t {
+            unit_id: unit_cfg.unit_id,
+            chan: channel,
+            atten: chan_cfg.atten,
+            bitwidth: chan_cfg.bitwidth,
+        };
+        let mut cali: esp_idf_sys::adc_cali_handle_t = core::ptr::null_mut();
+        let ret = esp_idf_sys::adc_cali_create_scheme_curve_fitting(&cali_cfg, &mut cali);
+        let cali = if ret == esp_idf_sys::ESP_OK {
+            log::info!("ADC calibration: curve fitting");
+            Some(cali)
+        } else {
+            log::warn!("ADC calibration unavailable ({}) — using linear conversion", ret);
+            None
+        };
+
+        let mut filtered_voltage: Option<f32> = None;
+        let mut charge = battery::ChargeDetector::new();
+        let mut gauge = battery::Gauge::new();
+        let mut last_history_flush = Instant::now();
+
         loop {
+            watchdog.feed();
             // ---- Check for sleep request (long-press) ----
             if sleep_requested.load(Ordering::SeqCst) {
-                enter_deep_sleep();
+                prepare_for_sleep(&sleep_requested, &sleep_ready);
+                save_state(store.as_mut(), &persistent);
+                sleep::enter_deep_sleep(SleepConfig::from_config());
             }
 
             // ---- Check inactivity timeout ----
-            let last = last_activity_ms.load(Ordering::Relaxed);
+            // (The UI task dims and switches off the screen before this.)
+            let (last, worn) = {
+                let state = state.lock().unwrap();
+                (state.last_activity_ms, state.worn)
+            };
             let now = crate::now_ms();
-            if now.wrapping_sub(last) > INACTIVITY_TIMEOUT_MS {
-                log::info!("Inactivity timeout ({} ms) — entering deep sleep", INACTIVITY_TIMEOUT_MS);
-                enter_deep_sleep();
+            if !charge.charging() && !worn && ticks::elapsed_ms(now, last) > DEEP_SLEEP_TIMEOUT_MS {
+                log::info!("Inactivity timeout ({} ms) — entering deep sleep", DEEP_SLEEP_TIMEOUT_MS);
+                prepare_for_sleep(&sleep_requested, &sleep_ready);
+                save_state(store.as_mut(), &persistent);
+                sleep::enter_deep_sleep(SleepConfig::from_config());
             }
 
             // ---- Read battery voltage ----
-            let mut raw: i32 = 0;
-            let ret = esp_idf_sys::adc_oneshot_read(handle, channel, &mut raw);
-            if ret == esp_idf_sys::ESP_OK {
-                // Assumes a 1:2 resistor divider before the ADC pin.
-                let voltage = (raw as f32 / 4095.0) * 3.3 * 2.0;
-                // Map LiPo range: 3.3 V = 0%, 4.2 V = 100%
-                let level = ((voltage - 3.3) / (4.2 - 3.3) * 100.0).clamp(0.0, 100.0);
-
-                let _ = ui_tx.send(UiEvent::UpdateBattery(level));
+            if let Some(raw) = read_battery_raw(handle, channel) {
+                let pin_voltage = raw_to_millivolts(cali, raw) / 1000.0;
+                if let Some(known) = BATTERY_CALIBRATION_VOLTAGE {
+                    if !battery::divider_calibrated() {
+                        if let Err(e) = battery::calibrate_divider(pin_voltage, known) {
+                            log::warn!("Battery divider calibration failed: {}", e);
+                        }
+                    }
+                }
+                let sample = pin_voltage * battery::divider_ratio();
+                let voltage = match filtered_voltage {
+                    Some(v) => v + BATTERY_EMA_ALPHA * (sample - v),
+                    None => sample,
+                };
+                filtered_voltage = Some(voltage);
+                let charge_change = charge.update(voltage);
+                let level = gauge.update(battery::percent_from_voltage(voltage), charge.charging());
+
+                {
+                    let mut state = state.lock().unwrap();
+                    state.battery_pct = Some(level);
+                    state.battery_v = Some(voltage);
+                }
+                let _ = ui_tx.send(UiEvent::UpdateBattery { pct: level, volts: voltage });
+
+                match charge_change {
+                    Some(true) => {
+                        log::info!("Charging started ({:.2} V)", voltage);
+                        state.lock().unwrap().charging = true;
+                        let _ = ui_tx.send(UiEvent::ChargingStarted);
+                    }
+                    Some(false) => {
+                        log::info!("Charging stopped ({:.2} V)", voltage);
+                        {
+                            let mut state = state.lock().unwrap();
+                            state.charging = false;
+                            // Restart the inactivity countdown from the unplug.
+                            state.last_activity_ms = crate::now_ms();
+                        }
+                        let _ = ui_tx.send(UiEvent::ChargingStopped);
+                    }
+                    None => {}
+                }
+            }
+
+            // ---- Flush the activity history ----
+            if last_history_flush.elapsed() >= Duration::from_millis(HISTORY_FLUSH_INTERVAL_MS) {
+                if let Some(store) = store.as_mut() {
+                    if let Err(e) = store.save_history() {
+                        log::warn!("Failed to save history to NVS: {}", e);
+                    }
+                }
+                last_history_flush = Instant::now();
             }
 
             thread::sleep(check_interval);
@@ -76,17 +182,75 @@ pub fn power_task(
     }
 }
 
-/// Configure GPIO wakeup on button press and enter deep sleep.
-/// This function does not return.
-fn enter_deep_sleep() -> ! {
-    log::info!("Entering deep sleep — wake on button press (GPIO{})", PIN_BUTTON);
-    unsafe {
-        esp_idf_sys::esp_deep_sleep_enable_gpio_wakeup(
-            1u64 << PIN_BUTTON,
-            esp_idf_sys::esp_deepsleep_gpio_wake_up_mode_t_ESP_GPIO_WAKEUP_GPIO_LOW,
-        );
-        esp_idf_sys::esp_deep_sleep_start();
+/// Median of up to `BATTERY_ADC_SAMPLES` raw readings, skipping any moment
+/// the haptic motor is running.  `None` if no clean reading could be taken
+/// (e.g. a long alert pattern was playing) — try again next interval.
+unsafe fn read_battery_raw(
+    handle: esp_idf_sys::adc_oneshot_unit_handle_t,
+    channel: esp_idf_sys::adc_channel_t,
+) -> Option<i32> {
+    let gap = Duration::from_millis(BATTERY_ADC_SAMPLE_GAP_MS);
+    let mut samples = [0i32; BATTERY_ADC_SAMPLES];
+    let mut count = 0;
+
+    for _ in 0..BATTERY_ADC_SAMPLES * 4 {
+        if count == BATTERY_ADC_SAMPLES {
+            break;
+        }
+        if !haptic::motor_active() {
+            let mut raw: i32 = 0;
+            if esp_idf_sys::adc_oneshot_read(handle, channel, &mut raw) == esp_idf_sys::ESP_OK {
+                samples[count] = raw;
+                count += 1;
+            }
+        }
+        thread::sleep(gap);
     }
-    // Never reached — but satisfies the `!` return type.
 
+    if count == 0 {
+        return None;
+    }
+    let samples = &mut samples[..count];
+    samples.sort_unstable();
+    Some(samples[count / 2])
 }
+
+/// ADC pin voltage in millivolts, calibrated when a scheme is available.
+unsafe fn raw_to_millivolts(cali: Option<esp_idf_sys::adc_cali_handle_t>, raw: i32) -> f32 {
+    if let Some(handle) = cali {
+        let mut mv: i32 = 0;
+        if esp_idf_sys::adc_cali_raw_to_voltage(handle, raw, &mut mv) == esp_idf_sys::ESP_OK {
+            return mv as f32;
+        }
+    }
+    raw as f32 / 4095.0 * 3300.0
+}
+
+/// Ask the other tasks to park their peripherals and wait until the UI task
+/// reports it is done (or `SLEEP_PREP_TIMEOUT_MS` passes — sleep regardless).
+fn prepare_for_sleep(sleep_requested: &AtomicBool, sleep_ready: &AtomicBool) {
+    sleep_requested.store(true, Ordering::SeqCst);
+
+    let deadline = Instant::now() + Duration::from_millis(SLEEP_PREP_TIMEOUT_MS);
+    while !sleep_ready.load(Ordering::SeqCst) {
+        if Instant::now() >= deadline {
+            log::warn!("UI did not confirm sleep preparation — sleeping anyway");
+            break;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// Persist the shared state before RAM is lost.
+fn save_state(store: Option<&mut Store>, persistent: &Mutex<PersistentState>) {
+    if let Some(store) = store {
+        let state = *persistent.lock().unwrap();
+        if let Err(e) = store.save(&state) {
+            log::warn!("Failed to save state to NVS: {}", e);
+        }
+        if let Err(e) = store.save_history() {
+            log::warn!("Failed to save history to NVS: {}", e);
+        }
+    }
+}
+
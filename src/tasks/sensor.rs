@@ -1,32 +1,237 @@
 // PlastiWatch V2 — Sensor Task
 //
-// Continuously reads 6-axis IMU data at ~62.5 Hz and pushes samples into the
-// sensor channel for the AI task to consume.
+// Continuously reads 6-axis IMU data and pushes samples into the sensor
+// channel for the AI task to consume.  The rate (~62.5 Hz normally) is read
+// each tick from `sample_interval_ms`, which the AI task lowers while the
+// wearer is idle.  With `SENSOR_IDLE_LOW_POWER` the slow rate also switches
+// the MPU6050 into its low-power accelerometer cycle mode.  The gyro is in
+// standby there and reads 0, which is fine: at the slow rate the AI task only
+// watches |a| for motion, and restoring the full rate brings the IMU back to
+// normal mode before the next window is filled.
+//
+// Ticks are scheduled against absolute deadlines (`next_tick += interval`),
+// so read time and sleep overshoot don't accumulate: the long-run rate is
+// exactly what the model was trained on.  If the task falls more than one
+// interval behind (e.g. a slow re-init) it resynchronises instead of
+// bursting to catch up.
+//
+// Failed reads are counted (see `health()`) and summarised in the log once a
+// minute while they keep happening.  A run of `SENSOR_REINIT_AFTER_ERRORS`
+// failures re-initialises the MPU6050, which recovers from a brownout that
+// reset its registers; the task gives up after `SENSOR_MAX_REINIT_ATTEMPTS`
+// re-inits in a row fail.  If the IMU doesn't even answer by then, the bus
+// itself is presumed wedged and is recovered (`drivers::bus::recover`) first.
+//
+// The IMU may also come loose (flex cable) or be swapped while running.  Every
+// `SENSOR_PROBE_INTERVAL_MS` a single short WHO_AM_I read (`Mpu6050::probe`,
+// one bus transaction, no retries) checks it is still there.  When it isn't —
+// or still isn't after a bus recovery — the task stops reading and only
+// probes at that cadence, with the bus free for the display in between.  Once
+// it answers again it is re-initialised and sampling resumes.  After
+// `SENSOR_DISCONNECTED_AFTER_MS` without an answer the UI is told
+// (`SensorLost`, then `SensorRestored` on reconnection), and `health()`
+// reports the IMU as disconnected so the AI task doesn't restart the chip
+// over the missing samples.  An IMU that fails to initialise at boot starts
+// out in this state.
+//
+// `request_calibration()` (the serial `calibrate` command, or the UI's zeroing
+// screen) makes the task measure fresh zero-point offsets before its next
+// read; sampling pauses meanwhile, so the measurement has the IMU to itself.
+// The outcome goes out as `UiEvent::CalibrationFinished`, and the UI keeps
+// successful offsets for NVS.  Offsets saved earlier (`initial_offsets`) are
+// applied at start-up.
+//
+// Every good sample goes through the `SENSOR_FILTER` stage (`filter`) before
+// anyone sees it.  The filter is reset whenever its history stops being
+// comparable: a re-init, a recalibration or a sample-rate change.
 
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::mpsc::Sender;
+use std::sync::Arc;
 use std::thread;
 use std::time::{Duration, Instant};
 
+use crate::broadcast::Broadcast;
 use crate::config::*;
-use crate::drivers::imu::{Mpu6050, SharedBus};
-use crate::events::SensorData;
+use crate::drivers::bus;
+use crate::drivers::imu::{CalibrationOffsets, Mpu6050, SharedBus};
+use crate::events::{SensorData, UiEvent};
+use crate::filter::ImuFilter;
+use crate::state::SharedState;
+use crate::watchdog;
+
+/// IMU reads that failed since boot (each one is a sample the AI never saw).
+static READ_ERRORS: AtomicU32 = AtomicU32::new(0);
+/// Failed reads since the last good one.
+static CONSECUTIVE_ERRORS: AtomicU32 = AtomicU32::new(0);
+/// IMU re-initialisations attempted since boot.
+static REINITS: AtomicU32 = AtomicU32::new(0);
+/// Set to have the sensor task recalibrate the IMU.
+static CALIBRATE_REQUESTED: AtomicBool = AtomicBool::new(false);
+/// The IMU isn't answering and the task is probing for it.
+static DISCONNECTED: AtomicBool = AtomicBool::new(false);
+
+/// Snapshot of the sensor task's error counters.
+#[derive(Debug, Clone, Copy)]
+pub struct SensorHealth {
+    pub read_errors: u32,
+    pub consecutive_errors: u32,
+    pub reinits: u32,
+    /// The IMU stopped answering; the task is waiting for it to come back.
+    pub disconnected: bool,
+}
+
+pub fn health() -> SensorHealth {
+    SensorHealth {
+        read_errors: READ_ERRORS.load(Ordering::Relaxed),
+        consecutive_errors: CONSECUTIVE_ERRORS.load(Ordering::Relaxed),
+        reinits: REINITS.load(Ordering::Relaxed),
+        disconnected: DISCONNECTED.load(Ordering::Relaxed),
+    }
+}
 
-pub fn sensor_task(bus: SharedBus, sensor_tx: Sender<SensorData>) {
+/// Ask the sensor task to recalibrate on its next tick.  The watch must lie
+/// still, face up, for ~2 s; the outcome is logged.
+pub fn request_calibration() {
+    CALIBRATE_REQUESTED.store(true, Ordering::Relaxed);
+}
+
+pub fn sensor_task(
+    bus: SharedBus,
+    sensor_tx: Sender<SensorData>,
+    sample_interval_ms: Arc<AtomicU64>,
+    state: SharedState,
+    ui_tx: Broadcast<UiEvent>,
+    initial_offsets: Option<CalibrationOffsets>,
+) {
     log::info!("Sensor task started");
+    let watchdog = watchdog::subscribe("sensor");
 
-    let imu = Mpu6050::new(bus);
-    if let Err(e) = imu.init() {
-        log::error!("MPU6050 init failed in sensor task: {}", e);
-        return;
+    let mut imu = Mpu6050::new(bus);
+    if let Some(offsets) = initial_offsets {
+        log::info!("MPU6050 offsets restored: {:?}", offsets);
+        imu.set_offsets(offsets);
     }
+    // When the IMU stopped answering, and whether the UI has been told.
+    let mut disconnected_since = None;
+    let mut disconnect_reported = false;
+    match imu.init() {
+        // A plausible die temperature is a cheap "sensor is alive" check.
+        Ok(()) => match imu.read_temperature() {
+            Ok(t) => log::info!("MPU6050 die temperature: {:.1} °C", t),
+            Err(e) => log::warn!("MPU6050 temperature read failed: {}", e),
+        },
+        Err(e) => {
+            log::error!("MPU6050 init failed in sensor task: {} — waiting for it", e);
+            disconnected_since = Some(Instant::now());
+            DISCONNECTED.store(true, Ordering::Relaxed);
+        }
+    }
+
+    let mut filter = ImuFilter::new(SENSOR_FILTER);
+    let mut filter_interval_ms = sample_interval_ms.load(Ordering::Relaxed);
+    let mut low_power = false;
+    let mut failed_reinits: u32 = 0;
+    let mut last_health_log = Instant::now();
+    let mut logged_errors: u32 = 0;
+    let probe_interval = Duration::from_millis(SENSOR_PROBE_INTERVAL_MS);
+    let mut last_probe = Instant::now();
 
-    let interval = Duration::from_millis(SENSOR_SAMPLE_INTERVAL_MS);
+    let mut next_tick = Instant::now();
 
     loop {
-        let tick_start = Instant::now();
+        watchdog.feed();
+
+        // Unplugged: only probe until the IMU answers again.
+        if let Some(since) = disconnected_since {
+            if imu.probe() {
+                match imu.init() {
+                    Ok(()) => {
+                        log::info!(
+                            "MPU6050 reconnected after {} s — resuming",
+                            since.elapsed().as_secs()
+                        );
+                        disconnected_since = None;
+                        DISCONNECTED.store(false, Ordering::Relaxed);
+                        CONSECUTIVE_ERRORS.store(0, Ordering::Relaxed);
+                        failed_reinits = 0;
+                        low_power = false;
+                        filter.reset();
+                        if std::mem::take(&mut disconnect_reported) {
+                            let _ = ui_tx.send(UiEvent::SensorRestored);
+                        }
+                        last_probe = Instant::now();
+                        next_tick = Instant::now();
+                        continue;
+                    }
+                    Err(e) => log::warn!("MPU6050 answers but init failed: {}", e),
+                }
+            } else if !disconnect_reported
+                && since.elapsed() >= Duration::from_millis(SENSOR_DISCONNECTED_AFTER_MS)
+            {
+                log::error!("MPU6050 disconnected for {} s", since.elapsed().as_secs());
+                disconnect_reported = true;
+                let _ = ui_tx.send(UiEvent::SensorLost);
+            }
+            thread::sleep(probe_interval);
+            continue;
+        }
+
+        if last_probe.elapsed() >= probe_interval {
+            last_probe = Instant::now();
+            if !imu.probe() {
+                log::error!("MPU6050 not answering — waiting for it to come back");
+                disconnected_since = Some(Instant::now());
+                DISCONNECTED.store(true, Ordering::Relaxed);
+                continue;
+            }
+        }
+
+        let interval_ms = sample_interval_ms.load(Ordering::Relaxed);
+        let want_low_power = SENSOR_IDLE_LOW_POWER && interval_ms >= SENSOR_IDLE_SAMPLE_INTERVAL_MS;
+        let calibrate = CALIBRATE_REQUESTED.swap(false, Ordering::Relaxed);
+
+        // Calibration needs the gyro, so it always runs in normal mode.
+        if low_power && (calibrate || !want_low_power) {
+            match imu.set_normal_mode() {
+                Ok(()) => low_power = false,
+                Err(e) => log::warn!("MPU6050 normal mode failed: {}", e),
+            }
+        } else if !low_power && want_low_power && !calibrate {
+            match imu.set_low_power_mode(SENSOR_IDLE_CYCLE_RATE) {
+                Ok(()) => low_power = true,
+                Err(e) => log::warn!("MPU6050 cycle mode failed: {}", e),
+            }
+        }
+
+        if calibrate {
+            let result = imu.calibrate(CALIBRATION_SAMPLES);
+            if let Err(e) = &result {
+                log::warn!("IMU calibration failed: {}", e);
+            }
+            let _ = ui_tx.send(UiEvent::CalibrationFinished(result.ok()));
+        }
+        if calibrate || interval_ms != filter_interval_ms {
+            filter.reset();
+            filter_interval_ms = interval_ms;
+        }
+
+        let interval = Duration::from_millis(interval_ms);
 
         match imu.read_data() {
-            Ok(data) => {
+            Ok(mut data) => {
+                filter.apply(&mut data, interval_ms);
+                CONSECUTIVE_ERRORS.store(0, Ordering::Relaxed);
+                failed_reinits = 0;
+                {
+                    let mut state = state.lock().unwrap();
+                    state.accel_g = Some([data.ax, data.ay, data.az]);
+                    state.temp_c = Some(data.temp_c);
+                }
+
+                #[cfg(feature = "capture")]
+                crate::capture::record(&data);
+
                 // Non-blocking send: if the AI task is behind, drop the oldest
                 // samples rather than blocking the sensor.
                 if sensor_tx.send(data).is_err() {
@@ -37,13 +242,68 @@ pub fn sensor_task(bus: SharedBus, sensor_tx: Sender<SensorData>) {
             }
             Err(e) => {
                 log::warn!("IMU read error: {}", e);
+                READ_ERRORS.fetch_add(1, Ordering::Relaxed);
+                let consecutive = CONSECUTIVE_ERRORS.fetch_add(1, Ordering::Relaxed) + 1;
+
+                if consecutive >= SENSOR_REINIT_AFTER_ERRORS {
+                    REINITS.fetch_add(1, Ordering::Relaxed);
+                    CONSECUTIVE_ERRORS.store(0, Ordering::Relaxed);
+                    // `init` leaves the IMU in normal mode; cycle mode is
+                    // re-entered on the next tick if still idle.
+                    low_power = false;
+                    filter.reset();
+                    if !imu.is_connected() {
+                        bus::recover(bus);
+                        if !imu.probe() {
+                            log::error!("MPU6050 gone after bus recovery — waiting for it");
+                            disconnected_since = Some(Instant::now());
+                            DISCONNECTED.store(true, Ordering::Relaxed);
+                            continue;
+                        }
+                    }
+                    match imu.init() {
+                        Ok(()) => {
+                            log::info!("MPU6050 re-initialised after {} failed reads", consecutive)
+                        }
+                        Err(e) => {
+                            failed_reinits += 1;
+                            log::error!(
+                                "MPU6050 re-init failed ({}/{}): {}",
+                                failed_reinits,
+                                SENSOR_MAX_REINIT_ATTEMPTS,
+                                e
+                            );
+                            if failed_reinits >= SENSOR_MAX_REINIT_ATTEMPTS {
+                                log::error!("MPU6050 unrecoverable — exiting sensor task");
+                                return;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        if last_health_log.elapsed() >= Duration::from_millis(SENSOR_HEALTH_LOG_INTERVAL_MS) {
+            let h = health();
+            if h.read_errors != logged_errors {
+                log::warn!(
+                    "Sensor health: {} failed reads ({} since last report), {} re-inits",
+                    h.read_errors,
+                    h.read_errors - logged_errors,
+                    h.reinits
+                );
+                logged_errors = h.read_errors;
             }
+            last_health_log = Instant::now();
         }
 
-        // Sleep for the remainder of the sampling interval to maintain ~62.5 Hz.
-        let elapsed = tick_start.elapsed();
-        if elapsed < interval {
-            thread::sleep(interval - elapsed);
+        // Sleep until the next deadline.
+        next_tick += interval;
+        let now = Instant::now();
+        if next_tick > now {
+            thread::sleep(next_tick - now);
+        } else if now - next_tick > interval {
+            next_tick = now;
         }
     }
 }
@@ -1,108 +1,843 @@
 // PlastiWatch V2 — UI Task
 //
-// Owns the OLED display, haptic motor, and button input manager.
+// Owns the OLED display, haptic motor, and button input manager.  The motor
+// runs on its own thread so a long buzz never stalls button polling.
 // Polls the button at ~100 Hz and processes UI events from the AI and power
-// tasks.
+// tasks.  Screens are redrawn into the display's buffer as events arrive, but
+// frames go out to the panel at most every `UI_REFRESH_INTERVAL_MS`, so a
+// burst of events costs one bus transfer and the IMU keeps the bus.
+//
+// A fall alert takes over the screen: "FALL DETECTED" scrolls across it, the
+// panel flashes (hardware invert) every `FALL_ALERT_PULSE_INTERVAL_MS` and the
+// motor repeats SOS ever more strongly until a click acknowledges it, which
+// returns to the activity screen.
+//
+// The activity screen shows the classifier's confidence as a bar under the
+// label, handy for spotting borderline classifications while tuning, and a
+// dot below it that moves along as the AI task's window fills.
+// A single click cycles default → activity → clock → summary → default; the
+// summary lists the time spent in each activity today, and a double-click
+// there clears it.
+// Double-click on the default screen toggles inverted rendering (easier to
+// read in bright sunlight); the setting holds on every screen and is saved.
+// Triple-click opens a hidden stats screen (uptime, steps, battery, heap);
+// another triple-click moves on to a diagnostics screen that re-probes the
+// OLED and IMU and shows live IMU, battery-voltage and temperature readings
+// plus the inference latency, and a third returns to the activity screen.
+// A single click leaves either for the default screen.
+// Double-click on the diagnostics screen zeroes the IMU in the field: a
+// `CALIBRATION_COUNTDOWN_S` countdown to lay the watch flat and let the click
+// buzz fade, then the sensor task measures (pausing sampling, so it has the
+// IMU to itself) and reports back; the outcome shows for
+// `CALIBRATION_RESULT_SHOW_MS` before the activity screen returns.  A click
+// cancels the countdown.  New offsets — from here or the serial `calibrate`
+// command — go into the persistent state and are saved with it.
+// Demo mode (serial `demo on`) is for showing the watch off the wrist: the
+// activity screen steps through every activity every `DEMO_STEP_INTERVAL_MS`
+// whatever the classifier says, and keeps the screen awake.  Any button
+// press, or `demo off`, returns to the live activity screen.
+// Holding the button shows a shrinking "powering off" ring until release.
+// A stuck button (see `input`) replaces the ring with a "button stuck" notice
+// until it is released.
+// Starting an activity flagged in `HAPTIC_ON_ACTIVITY` (walking, running)
+// gets a heartbeat pulse; the AI task only reports changes, so a steady
+// activity doesn't buzz again.
+// Below `HAPTIC_LOW_BATTERY_PCT` (and not charging) the motor is limited to
+// shorter, weaker pulses to avoid a brownout.
+// While charging the battery icon animates.  When the AI task reports the
+// watch is worn the other way up, the panel is flipped 180° in hardware.
+//
+// Staged power saving, all timed from the shared `last_activity_ms` (button
+// presses and wrist raises here, movement in the AI task): after
+// `DIM_TIMEOUT_MS` the OLED drops to `OLED_DIM_BRIGHTNESS`, after
+// `SCREEN_OFF_TIMEOUT_MS` the panel is switched off, and the loop polls more
+// slowly in both; the power task enters deep sleep at
+// `DEEP_SLEEP_TIMEOUT_MS`.  Any new activity brings the screen straight back.
+// A wrist raise that wakes the screen also switches to (and redraws) the
+// activity screen.
+//
+// When the power task raises `sleep_requested` the UI lets any confirmation
+// buzz finish, stops the motor, turns the OLED off, arms the MPU6050 motion
+// interrupt (with `MOTION_WAKE_ENABLED`) and sets `sleep_ready`.
 
-use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
-use std::sync::mpsc::{Receiver, Sender};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use esp_idf_hal::gpio::{AnyInputPin, AnyOutputPin, Input, Output, PinDriver};
+use esp_idf_hal::gpio::{AnyInputPin, Input, PinDriver};
+use esp_idf_hal::ledc::LedcDriver;
 
+use crate::broadcast::Broadcast;
 use crate::config::*;
-use crate::drivers::display::{OledDisplay, SharedBus};
-use crate::drivers::haptic::HapticDriver;
+use crate::drivers::display::{CalibrationView, OledDisplay, SharedBus};
+use crate::drivers::haptic::{self, HapticDriver, HapticHandle, HapticPattern};
+use crate::drivers::imu::Mpu6050;
 use crate::events::{ActivityClass, UiEvent};
 use crate::input::InputManager;
+use crate::nvs::PersistentState;
+use crate::state::SharedState;
+use crate::tasks;
+use crate::ticks::{self, SystemClock};
+use crate::watchdog;
+
+/// Power-saving stage of the panel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScreenStage {
+    Awake,
+    Dimmed,
+    Off,
+}
 
+/// What the display is currently showing (overlays such as the fall alert and
+/// capture screen take precedence).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Screen {
+    /// Logo + "PlastiBytes".
+    Default,
+    Activity,
+    /// Time of day.
+    Clock,
+    /// Time spent in each activity (`SystemState::dwell`).
+    Summary,
+    /// Hidden debug/stats screen (triple-click).
+    Stats,
+    /// Live hardware check (triple-click from the stats screen).
+    Diagnostics,
+    /// Activity screen for `ActivityClass::from_index(n)`, whatever the
+    /// classifier says (demo mode).
+    Demo(usize),
+    /// IMU zeroing flow (double-click on the diagnostics screen).
+    Calibration(CalibrationStep),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CalibrationStep {
+    /// Counting down since the instant; the seconds left last drawn.
+    Countdown(Instant, u32),
+    /// Measurement requested from the sensor task at the instant.
+    Measuring(Instant),
+    /// Outcome (`true` = new offsets in use) shown since the instant.
+    Done(Instant, bool),
+}
+
+/// The latest figures from the other tasks, as the activity screen shows them.
+#[derive(Debug, Clone, Copy)]
+struct Readings {
+    activity: ActivityClass,
+    /// Confidence of the latest window in `activity` (0.0–1.0).
+    confidence: f32,
+    battery_pct: f32,
+    steps: u32,
+}
+
+/// What the diagnostics screen reads besides the display itself.  The IMU is
+/// only probed (one register read under the bus lock); live values come from
+/// the sensor task through the shared state.  The same handle arms the
+/// motion-wake interrupt before deep sleep.
+struct Probe {
+    imu: Mpu6050,
+    state: SharedState,
+}
+
+/// An unacknowledged fall alert.
+struct FallAlert {
+    pulses: u32,
+    last_pulse: Instant,
+    inverted: bool,
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn ui_task(
     bus: SharedBus,
     button_pin: PinDriver<'static, AnyInputPin, Input>,
-    haptic_pin: PinDriver<'static, AnyOutputPin, Output>,
+    haptic_pwm: LedcDriver<'static>,
     ui_rx: Receiver<UiEvent>,
-    ui_tx: Sender<UiEvent>,
+    ui_tx: Broadcast<UiEvent>,
     sleep_requested: Arc<AtomicBool>,
-    last_activity_ms: Arc<AtomicU32>,
+    sleep_ready: Arc<AtomicBool>,
+    state: SharedState,
+    persistent: Arc<Mutex<PersistentState>>,
 ) {
     log::info!("UI task started");
+    let watchdog = watchdog::subscribe("ui");
+    let saved = *persistent.lock().unwrap();
 
     let mut display = OledDisplay::new(bus);
-    let mut haptic = HapticDriver::new(haptic_pin);
-    let mut input = InputManager::new(button_pin, ui_tx);
+    display.set_frame_interval(UI_REFRESH_INTERVAL_MS);
+    let haptic = match HapticDriver::new(haptic_pwm).spawn() {
+        Ok(h) => h,
+        Err(e) => {
+            log::error!("Failed to start haptic thread: {}", e);
+            return;
+        }
+    };
+    let mut input = InputManager::new(button_pin, ui_tx, SystemClock, saved.input);
+    let probe = Probe {
+        imu: Mpu6050::new(bus),
+        state: state.clone(),
+    };
 
     // Start on the default UI (logo + PlastiBytes text).
-    let mut showing_logo = true;
-    let mut current_activity = ActivityClass::default();
-    let mut current_battery: f32 = 100.0;
+    let mut screen = Screen::Default;
+    let mut readings = Readings {
+        activity: saved.last_activity,
+        confidence: 0.0,
+        battery_pct: 100.0,
+        steps: saved.steps,
+    };
+    let mut fall_alert: Option<FallAlert> = None;
+    let mut hold_ring = false;
 
+    let _ = display.set_brightness(saved.brightness);
+    let _ = display.set_invert(saved.inverted);
     if let Err(e) = display.show_default_ui() {
         log::error!("Display error: {}", e);
     }
 
     let poll_interval = Duration::from_millis(UI_POLL_INTERVAL_MS);
+    let dim_poll_interval = Duration::from_millis(UI_DIM_POLL_INTERVAL_MS);
+    let mut last_stats_refresh = Instant::now();
+    let mut last_clock_refresh = Instant::now();
+    let mut last_scroll_step = Instant::now();
+    let mut charging = false;
+    let mut stage = ScreenStage::Awake;
+    let mut last_charge_frame = Instant::now();
+    let mut last_demo_step = Instant::now();
+    #[cfg(feature = "capture")]
+    let mut last_capture_refresh = Instant::now();
+    #[cfg(feature = "capture")]
+    let mut capture_shown = false;
 
     loop {
+        watchdog.feed();
         // 1. Poll the button (handles debounce + click detection internally).
         input.update();
 
         // 2. Drain all pending UI events (non-blocking).
         while let Ok(event) = ui_rx.try_recv() {
+            // Whether this event brought the screen back from dimmed / off.
+            let mut woke = false;
+            if is_user_event(&event) {
+                state.lock().unwrap().last_activity_ms = crate::now_ms();
+                if stage != ScreenStage::Awake && !sleep_requested.load(Ordering::SeqCst) {
+                    woke = true;
+                    set_stage(&mut display, &mut stage, ScreenStage::Awake);
+                }
+            }
+
+            // A button press only ends demo mode.
+            if matches!(screen, Screen::Demo(_))
+                && is_user_event(&event)
+                && !matches!(event, UiEvent::WristRaised)
+            {
+                log::info!("Demo mode off");
+                screen = Screen::Activity;
+                show_screen(&mut display, screen, &readings, &probe);
+                continue;
+            }
+
             match event {
-                UiEvent::UpdateActivity(activity) => {
-                    current_activity = activity;
-                    if !showing_logo {
-                        let _ = display.show_activity(current_activity, current_battery);
+                UiEvent::UpdateActivity(activity, confidence) => {
+                    if activity != readings.activity && HAPTIC_ON_ACTIVITY[activity.index()] {
+                        haptic.play(HapticPattern::Heartbeat);
+                    }
+                    readings.activity = activity;
+                    readings.confidence = confidence;
+                    persistent.lock().unwrap().last_activity = activity;
+                    if activity_shown(screen, &fall_alert, hold_ring) {
+                        refresh_activity(&mut display, &readings);
+                    }
+                }
+
+                UiEvent::FallDetected => {
+                    if fall_alert.is_none() {
+                        log::warn!("Fall detected — alerting until acknowledged");
+                        fall_alert = Some(FallAlert {
+                            pulses: 0,
+                            last_pulse: Instant::now(),
+                            inverted: false,
+                        });
+                        state.lock().unwrap().last_activity_ms = crate::now_ms();
+                        set_stage(&mut display, &mut stage, ScreenStage::Awake);
+                        let _ = display.show_fall_alert();
+                        haptic.play_intensity(HapticPattern::SosMorse, FALL_ALERT_START_INTENSITY);
+                    }
+                }
+
+                UiEvent::UpdateBattery { pct, volts } => {
+                    log::debug!("Battery {:.0}% ({:.2} V)", pct, volts);
+                    readings.battery_pct = pct;
+                    haptic::set_low_battery(!charging && pct < HAPTIC_LOW_BATTERY_PCT);
+                    if activity_shown(screen, &fall_alert, hold_ring) {
+                        refresh_activity(&mut display, &readings);
+                    }
+                }
+
+                UiEvent::UpdateSteps(steps) => {
+                    readings.steps = steps;
+                    persistent.lock().unwrap().steps = steps;
+                    if activity_shown(screen, &fall_alert, hold_ring) {
+                        refresh_activity(&mut display, &readings);
+                    }
+                }
+
+                UiEvent::ChargingStarted | UiEvent::ChargingStopped => {
+                    charging = matches!(event, UiEvent::ChargingStarted);
+                    display.set_charging(charging);
+                    let low = readings.battery_pct < HAPTIC_LOW_BATTERY_PCT;
+                    haptic::set_low_battery(!charging && low);
+                    if charging {
+                        haptic.trigger();
+                        last_charge_frame = Instant::now();
+                    }
+                    if activity_shown(screen, &fall_alert, hold_ring) {
+                        refresh_activity(&mut display, &readings);
+                    }
+                }
+
+                UiEvent::SensorLost | UiEvent::SensorRestored => {
+                    display.set_sensor_lost(matches!(event, UiEvent::SensorLost));
+                    if activity_shown(screen, &fall_alert, hold_ring) {
+                        refresh_activity(&mut display, &readings);
+                    }
+                }
+
+                UiEvent::WindowProgress(progress) => {
+                    display.set_window_progress(progress);
+                    if activity_shown(screen, &fall_alert, hold_ring) {
+                        refresh_activity(&mut display, &readings);
                     }
                 }
 
-                UiEvent::UpdateBattery(level) => {
-                    current_battery = level;
-                    if !showing_logo {
-                        let _ = display.show_activity(current_activity, current_battery);
+                UiEvent::SetBrightness(level) => {
+                    let _ = display.set_brightness(level);
+                    persistent.lock().unwrap().brightness = level;
+                }
+
+                UiEvent::SetInvert(inverted) => {
+                    if inverted != display.inverted() {
+                        toggle_invert(&mut display, &persistent);
+                    }
+                }
+
+                UiEvent::SetDemo(on) => {
+                    if on == matches!(screen, Screen::Demo(_)) {
+                        continue;
+                    }
+                    log::info!("Demo mode {}", if on { "on" } else { "off" });
+                    screen = if on { Screen::Demo(0) } else { Screen::Activity };
+                    last_demo_step = Instant::now();
+                    if !capture_active() && fall_alert.is_none() && !hold_ring {
+                        show_screen(&mut display, screen, &readings, &probe);
                     }
                 }
 
                 UiEvent::ButtonSingleClick => {
                     haptic.trigger();
-                    last_activity_ms.store(crate::now_ms(), Ordering::Relaxed);
 
-                    // Toggle between default UI and activity screen.
-                    showing_logo = !showing_logo;
-                    if showing_logo {
-                        let _ = display.show_default_ui();
-                    } else {
-                        let _ = display.show_activity(current_activity, current_battery);
+                    if fall_alert.is_some() {
+                        acknowledge_fall(&mut display, &haptic, &mut fall_alert);
+                        screen = Screen::Activity;
+                        refresh_activity(&mut display, &readings);
+                        continue;
+                    }
+
+                    // Any click ends an in-progress capture.
+                    #[cfg(feature = "capture")]
+                    if crate::capture::is_active() {
+                        crate::capture::stop();
+                        screen = Screen::Activity;
+                        refresh_activity(&mut display, &readings);
+                        continue;
                     }
+
+                    // Cycle default → activity → clock → summary → default (a
+                    // click also leaves the stats screen).
+                    screen = match screen {
+                        Screen::Default => Screen::Activity,
+                        Screen::Activity => Screen::Clock,
+                        Screen::Clock => Screen::Summary,
+                        Screen::Summary
+                        | Screen::Stats
+                        | Screen::Diagnostics
+                        | Screen::Demo(_)
+                        | Screen::Calibration(_) => Screen::Default,
+                    };
+                    show_screen(&mut display, screen, &readings, &probe);
+                    last_clock_refresh = Instant::now();
                 }
 
                 UiEvent::ButtonDoubleClick => {
-                    haptic.trigger();
-                    last_activity_ms.store(crate::now_ms(), Ordering::Relaxed);
+                    haptic.play(HapticPattern::DoubleTap);
+
+                    if fall_alert.is_some() {
+                        acknowledge_fall(&mut display, &haptic, &mut fall_alert);
+                        screen = Screen::Activity;
+                        refresh_activity(&mut display, &readings);
+                        continue;
+                    }
+
+                    // Double-click on the default screen toggles inverted
+                    // (dark-on-light) rendering.
+                    if screen == Screen::Default && !capture_active() {
+                        toggle_invert(&mut display, &persistent);
+                        continue;
+                    }
+
+                    // Double-click on the diagnostics screen zeroes the IMU.
+                    if screen == Screen::Diagnostics && !capture_active() {
+                        log::info!("IMU zeroing: counting down");
+                        screen = Screen::Calibration(CalibrationStep::Countdown(
+                            Instant::now(),
+                            CALIBRATION_COUNTDOWN_S,
+                        ));
+                        show_screen(&mut display, screen, &readings, &probe);
+                        continue;
+                    }
+
+                    // Double-click on the summary screen clears the tally.
+                    if screen == Screen::Summary && !capture_active() {
+                        log::info!("Activity tally reset");
+                        state.lock().unwrap().dwell.reset(crate::now_ms());
+                        show_screen(&mut display, screen, &readings, &probe);
+                        continue;
+                    }
+
+                    // Double-click starts / stops training-data capture.
+                    #[cfg(feature = "capture")]
+                    if crate::capture::is_active() {
+                        crate::capture::stop();
+                    } else {
+                        crate::capture::start();
+                        let _ = display.show_capture(0);
+                        last_capture_refresh = Instant::now();
+                        continue;
+                    }
 
                     // Force activity display.
-                    showing_logo = false;
-                    let _ = display.show_activity(current_activity, current_battery);
+                    screen = Screen::Activity;
+                    refresh_activity(&mut display, &readings);
+                }
+
+                UiEvent::ButtonTripleClick => {
+                    haptic.trigger();
+                    if fall_alert.is_some() || capture_active() {
+                        continue;
+                    }
+
+                    // Step through the hidden stats and diagnostics screens.
+                    screen = match screen {
+                        Screen::Stats => Screen::Diagnostics,
+                        Screen::Diagnostics => Screen::Activity,
+                        _ => Screen::Stats,
+                    };
+                    show_screen(&mut display, screen, &readings, &probe);
+                    last_stats_refresh = Instant::now();
+                }
+
+                UiEvent::WristRaised => {
+                    // Raise to wake: bring up (or refresh) the activity screen
+                    // without a press.
+                    if (screen == Screen::Default || woke)
+                        && !capture_active()
+                        && fall_alert.is_none()
+                        && !hold_ring
+                    {
+                        screen = Screen::Activity;
+                        refresh_activity(&mut display, &readings);
+                    }
+                }
+
+                UiEvent::CalibrationFinished(offsets) => {
+                    if let Some(offsets) = offsets {
+                        persistent.lock().unwrap().imu_offsets = Some(offsets);
+                    }
+                    if let Screen::Calibration(CalibrationStep::Measuring(_)) = screen {
+                        let ok = offsets.is_some();
+                        screen = Screen::Calibration(CalibrationStep::Done(Instant::now(), ok));
+                        haptic.play(if ok {
+                            HapticPattern::DoubleTap
+                        } else {
+                            HapticPattern::LongBuzz
+                        });
+                        if !capture_active() && fall_alert.is_none() && !hold_ring {
+                            show_screen(&mut display, screen, &readings, &probe);
+                        }
+                    }
+                }
+
+                UiEvent::OrientationChanged(flipped) => {
+                    let _ = display.set_rotated(flipped);
+                }
+
+                UiEvent::ButtonHoldProgress(progress) => {
+                    if fall_alert.is_none() && !capture_active() {
+                        hold_ring = true;
+                        let _ = display.show_hold_progress(progress);
+                    }
+                }
+
+                UiEvent::ButtonHoldCancelled => {
+                    if std::mem::take(&mut hold_ring) && fall_alert.is_none() && !capture_active() {
+                        show_screen(&mut display, screen, &readings, &probe);
+                    }
+                }
+
+                UiEvent::ButtonStuck => {
+                    hold_ring = false;
+                    if fall_alert.is_none() && !capture_active() {
+                        let _ = display.show_centered_text(crate::i18n::strings().button_stuck);
+                    }
+                }
+
+                UiEvent::ButtonRecovered => {
+                    if fall_alert.is_none() && !capture_active() {
+                        show_screen(&mut display, screen, &readings, &probe);
+                    }
+                }
+
+                UiEvent::ButtonRepeat => {
+                    // Reserved for menu scrolling; repeat is off by default.
                 }
 
                 UiEvent::ButtonLongPress => {
                     // 3-second hold → power off.
-                    haptic.buzz(Duration::from_millis(500));
-                    let _ = display.turn_off();
+                    haptic.play(HapticPattern::LongBuzz);
                     sleep_requested.store(true, Ordering::SeqCst);
                     log::info!("Long press detected — requesting deep sleep");
                 }
             }
         }
 
-        // 3. If sleep was requested, stop refreshing (power task handles sleep entry).
+        // Dim, then switch off, the panel as inactivity grows; undo it as
+        // soon as there is activity again (never during a fall alert, and
+        // left alone once the panel is being parked for sleep).
+        if !sleep_requested.load(Ordering::SeqCst) {
+            let idle_ms = ticks::ms_since(state.lock().unwrap().last_activity_ms);
+            let target = if fall_alert.is_some() || idle_ms < DIM_TIMEOUT_MS {
+                ScreenStage::Awake
+            } else if idle_ms < SCREEN_OFF_TIMEOUT_MS {
+                ScreenStage::Dimmed
+            } else {
+                ScreenStage::Off
+            };
+            set_stage(&mut display, &mut stage, target);
+        }
+
+        // Escalate an unacknowledged fall alert: flash and pulse ever longer.
+        if let Some(alert) = fall_alert.as_mut() {
+            if last_scroll_step.elapsed() >= Duration::from_millis(SCROLL_STEP_INTERVAL_MS) {
+                let _ = display.show_fall_alert();
+                last_scroll_step = Instant::now();
+            }
+
+            if alert.last_pulse.elapsed() >= Duration::from_millis(FALL_ALERT_PULSE_INTERVAL_MS) {
+                alert.pulses += 1;
+                alert.last_pulse = Instant::now();
+                alert.inverted = !alert.inverted;
+                let _ = display.set_flash(alert.inverted);
+
+                if alert.pulses % FALL_ALERT_SOS_EVERY_PULSES == 0 {
+                    let repeats = alert.pulses / FALL_ALERT_SOS_EVERY_PULSES;
+                    let intensity = (FALL_ALERT_START_INTENSITY as u32
+                        + repeats * FALL_ALERT_INTENSITY_STEP as u32)
+                        .min(u8::MAX as u32) as u8;
+                    haptic.play_intensity(HapticPattern::SosMorse, intensity);
+                }
+
+                // Never sleep on a user who may be down.
+                state.lock().unwrap().last_activity_ms = crate::now_ms();
+            }
+        }
+
+        // Keep the clock ticking.
+        if screen == Screen::Clock
+            && !capture_active()
+            && fall_alert.is_none()
+            && !hold_ring
+            && last_clock_refresh.elapsed() >= Duration::from_millis(CLOCK_REFRESH_INTERVAL_MS)
+        {
+            let _ = display.show_clock(crate::clock::now(), readings.battery_pct);
+            last_clock_refresh = Instant::now();
+        }
+
+        // Keep the stats, diagnostics and summary readings live.
+        if matches!(screen, Screen::Stats | Screen::Diagnostics | Screen::Summary)
+            && !capture_active()
+            && fall_alert.is_none()
+            && !hold_ring
+            && last_stats_refresh.elapsed() >= Duration::from_millis(STATS_REFRESH_INTERVAL_MS)
+        {
+            show_screen(&mut display, screen, &readings, &probe);
+            last_stats_refresh = Instant::now();
+        }
+
+        // Move the zeroing screen along: tick the countdown, then ask the
+        // sensor task to measure, give up if it never answers, and return to
+        // the activity screen once the outcome has been seen.
+        if let Screen::Calibration(step) = screen {
+            let next = match step {
+                CalibrationStep::Countdown(since, shown) => match countdown_left(since) {
+                    0 => {
+                        log::info!("IMU zeroing: measuring");
+                        tasks::sensor::request_calibration();
+                        Some(Screen::Calibration(CalibrationStep::Measuring(Instant::now())))
+                    }
+                    left if left != shown => {
+                        Some(Screen::Calibration(CalibrationStep::Countdown(since, left)))
+                    }
+                    _ => None,
+                },
+                CalibrationStep::Measuring(since)
+                    if since.elapsed() >= Duration::from_millis(CALIBRATION_UI_TIMEOUT_MS) =>
+                {
+                    log::warn!("IMU zeroing: no result from the sensor task");
+                    haptic.play(HapticPattern::LongBuzz);
+                    Some(Screen::Calibration(CalibrationStep::Done(Instant::now(), false)))
+                }
+                CalibrationStep::Done(since, _)
+                    if since.elapsed() >= Duration::from_millis(CALIBRATION_RESULT_SHOW_MS) =>
+                {
+                    Some(Screen::Activity)
+                }
+                _ => None,
+            };
+            if let Some(next) = next {
+                screen = next;
+                if !capture_active() && fall_alert.is_none() && !hold_ring {
+                    show_screen(&mut display, screen, &readings, &probe);
+                }
+            }
+        }
+
+        // Step the demo on to the next activity; a demo on a stand should
+        // neither dim nor sleep.
+        if let Screen::Demo(index) = screen {
+            if !capture_active()
+                && fall_alert.is_none()
+                && !hold_ring
+                && last_demo_step.elapsed() >= Duration::from_millis(DEMO_STEP_INTERVAL_MS)
+            {
+                screen = Screen::Demo((index + 1) % (EI_LABEL_COUNT + 1));
+                show_screen(&mut display, screen, &readings, &probe);
+                last_demo_step = Instant::now();
+                state.lock().unwrap().last_activity_ms = crate::now_ms();
+            }
+        }
+
+        // Animate the battery icon while charging (paused while dimmed / off).
+        if charging
+            && stage == ScreenStage::Awake
+            && activity_shown(screen, &fall_alert, hold_ring)
+            && last_charge_frame.elapsed() >= Duration::from_millis(CHARGING_ANIMATION_INTERVAL_MS)
+        {
+            display.advance_charge_animation();
+            refresh_activity(&mut display, &readings);
+            last_charge_frame = Instant::now();
+        }
+
+        // Keep the "REC" sample counter live while capturing.
+        #[cfg(feature = "capture")]
+        if crate::capture::is_active()
+            && fall_alert.is_none()
+            && last_capture_refresh.elapsed() >= Duration::from_millis(CAPTURE_REFRESH_INTERVAL_MS)
+        {
+            let _ = display.show_capture(crate::capture::sample_count());
+            last_capture_refresh = Instant::now();
+            capture_shown = true;
+        }
+
+        // A capture stopped from the serial console leaves "REC" on screen.
+        #[cfg(feature = "capture")]
+        if capture_shown && !crate::capture::is_active() && fall_alert.is_none() {
+            capture_shown = false;
+            show_screen(&mut display, screen, &readings, &probe);
+        }
+
+        // Send whatever the frame-rate cap held back.
+        let _ = display.present();
+
+        // 3. If sleep was requested, park the peripherals once, then stop
+        //    refreshing (power task handles sleep entry).
         if sleep_requested.load(Ordering::SeqCst) {
+            if !sleep_ready.load(Ordering::SeqCst) {
+                prepare_for_sleep(&display, &haptic, &probe.imu, readings.steps);
+                sleep_ready.store(true, Ordering::SeqCst);
+            }
             thread::sleep(Duration::from_secs(1));
             continue;
         }
 
-        thread::sleep(poll_interval);
+        thread::sleep(if stage == ScreenStage::Awake { poll_interval } else { dim_poll_interval });
+    }
+}
+
+/// Move the panel to power-saving stage `to` (no-op if already there).  The
+/// panel keeps its RAM while off, so waking it needs no redraw.
+fn set_stage(display: &mut OledDisplay, stage: &mut ScreenStage, to: ScreenStage) {
+    if *stage == to {
+        return;
+    }
+    log::debug!("Screen {:?} → {:?}", stage, to);
+    if *stage == ScreenStage::Off {
+        let _ = display.turn_on();
+    }
+    let _ = match to {
+        ScreenStage::Awake => display.set_dimmed(false),
+        ScreenStage::Dimmed => display.set_dimmed(true),
+        ScreenStage::Off => display.turn_off(),
+    };
+    *stage = to;
+}
+
+/// Clear the fall alert, silence the SOS and undo the screen flash.
+fn acknowledge_fall(
+    display: &mut OledDisplay,
+    haptic: &HapticHandle,
+    fall_alert: &mut Option<FallAlert>,
+) {
+    log::info!("Fall alert acknowledged");
+    *fall_alert = None;
+    haptic.stop();
+    let _ = display.set_flash(false);
+}
+
+/// Flip the user invert setting and remember it across sleep.
+fn toggle_invert(display: &mut OledDisplay, persistent: &Mutex<PersistentState>) {
+    let inverted = !display.inverted();
+    let _ = display.set_invert(inverted);
+    persistent.lock().unwrap().inverted = inverted;
+    log::info!("Display {}", if inverted { "inverted" } else { "normal" });
+}
+
+/// Events caused by the user deliberately interacting with the watch.
+fn is_user_event(event: &UiEvent) -> bool {
+    matches!(
+        event,
+        UiEvent::ButtonSingleClick
+            | UiEvent::ButtonDoubleClick
+            | UiEvent::ButtonTripleClick
+            | UiEvent::ButtonLongPress
+            | UiEvent::ButtonHoldProgress(_)
+            | UiEvent::ButtonHoldCancelled
+            | UiEvent::ButtonRepeat
+            | UiEvent::WristRaised
+    )
+}
+
+/// Leave the motor off and the panel dark ahead of deep sleep.
+fn prepare_for_sleep(display: &OledDisplay, haptic: &HapticHandle, imu: &Mpu6050, steps: u32) {
+    #[cfg(feature = "ble")]
+    crate::ble::pause();
+
+    // Let a short confirmation (the long-press buzz) finish, but don't wait
+    // out an alert pattern.
+    let grace = Instant::now() + Duration::from_millis(SLEEP_HAPTIC_GRACE_MS);
+    while haptic::motor_active() && Instant::now() < grace {
+        thread::sleep(Duration::from_millis(10));
+    }
+    haptic.stop();
+    while haptic::motor_active() && Instant::now() < grace + Duration::from_millis(100) {
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    let _ = display.turn_off();
+    if MOTION_WAKE_ENABLED {
+        if let Err(e) =
+            imu.enable_motion_interrupt(MOTION_WAKE_THRESHOLD_MG, MOTION_WAKE_DURATION_MS)
+        {
+            log::warn!("Motion wake unavailable: {}", e);
+        }
+    }
+    log::info!("UI ready for sleep ({} steps this session)", steps);
+}
+
+/// Whether the activity screen is showing with nothing (fall alert, capture,
+/// hold ring) drawn over it, so a change to `Readings` should redraw it.
+fn activity_shown(screen: Screen, fall_alert: &Option<FallAlert>, hold_ring: bool) -> bool {
+    screen == Screen::Activity && !capture_active() && fall_alert.is_none() && !hold_ring
+}
+
+/// Draw the activity screen with the latest readings.
+fn refresh_activity(display: &mut OledDisplay, readings: &Readings) {
+    let _ = display.show_activity(
+        readings.activity,
+        readings.confidence,
+        readings.battery_pct,
+        readings.steps,
+    );
+}
+
+/// Redraw `screen` from scratch (after an overlay goes away).
+fn show_screen(display: &mut OledDisplay, screen: Screen, readings: &Readings, probe: &Probe) {
+    let Readings {
+        activity,
+        confidence,
+        battery_pct,
+        steps,
+    } = *readings;
+
+    match screen {
+        Screen::Default => {
+            let _ = display.show_default_ui();
+        }
+        Screen::Activity => {
+            let _ = display.show_activity(activity, confidence, battery_pct, steps);
+        }
+        Screen::Demo(index) => {
+            let demo = ActivityClass::from_index(index);
+            let _ = display.show_activity(demo, 1.0, battery_pct, steps);
+        }
+        Screen::Clock => {
+            let _ = display.show_clock(crate::clock::now(), battery_pct);
+        }
+        Screen::Summary => {
+            let totals = probe.state.lock().unwrap().dwell.totals(crate::now_ms());
+            let _ = display.show_summary(&totals);
+        }
+        Screen::Stats => show_stats(display, steps, battery_pct),
+        Screen::Calibration(step) => {
+            let view = match step {
+                CalibrationStep::Countdown(since, _) => {
+                    CalibrationView::Countdown(countdown_left(since))
+                }
+                CalibrationStep::Measuring(_) => CalibrationView::Measuring,
+                CalibrationStep::Done(_, ok) => CalibrationView::Done(ok),
+            };
+            let _ = display.show_calibration(view);
+        }
+        Screen::Diagnostics => {
+            let oled_ok = display.is_connected();
+            let imu_ok = probe.imu.is_connected();
+            let state = *probe.state.lock().unwrap();
+            let _ = display.show_diagnostics(oled_ok, imu_ok, &state, ticks::uptime_s());
+        }
     }
 }
+
+/// Whole seconds left of a zeroing countdown started at `since` (rounded up,
+/// so it reads 3, 2, 1 and hits 0 when time is up).
+fn countdown_left(since: Instant) -> u32 {
+    let total = Duration::from_secs(CALIBRATION_COUNTDOWN_S as u64);
+    total.saturating_sub(since.elapsed()).as_millis().div_ceil(1000) as u32
+}
+
+/// Draw the stats screen with live uptime and heap figures.
+fn show_stats(display: &mut OledDisplay, steps: u32, battery_pct: f32) {
+    let uptime_s = ticks::uptime_s();
+    let free_heap = unsafe { esp_idf_sys::esp_get_free_heap_size() };
+    let _ = display.show_stats(uptime_s, steps, battery_pct, free_heap);
+}
+
+/// Whether training-data capture currently owns the screen.
+#[cfg(feature = "capture")]
+fn capture_active() -> bool {
+    crate::capture::is_active()
+}
+
+#[cfg(not(feature = "capture"))]
+fn capture_active() -> bool {
+    false
+}
@@ -0,0 +1,98 @@
+// PlastiWatch V2 — Monotonic Time Source
+//
+// Timing logic (button debounce and click windows, the AI task's sensor
+// timeout and activity timestamps) reads milliseconds since boot through the
+// `Clock` trait instead of calling `now_ms()` directly, so a test harness can
+// substitute a clock it steps by hand and check the timing deterministically.
+//
+// The firmware passes `SystemClock`, a zero-sized wrapper around `now_ms()`.
+// Users are generic over the clock, so this compiles down to the direct call.
+// Test builds (host-tests/) get `MockClock` instead, and lose the items that
+// read the hardware timer.
+//
+// Readings are u32 and wrap after ~49.7 days.  Every interval between two of
+// them goes through `elapsed_ms` (or `ms_since` / `Clock::ms_since` against
+// the current time), which subtracts with wrap-around: 0x0000_0010 is 0x20 ms
+// after 0xFFFF_FFF0.  That is right for any interval shorter than the wrap,
+// which covers every timeout and stretch the firmware measures.  Plain `-`
+// or `<` on two readings is wrong across the wrap — don't.  Uptime figures
+// that can exceed the wrap come from `uptime_s`, which reads the 64-bit timer.
+
+/// Milliseconds since boot.
+pub trait Clock {
+    fn now_ms(&self) -> u32;
+
+    /// Milliseconds since `earlier`, a reading of this clock.
+    #[inline]
+    fn ms_since(&self, earlier: u32) -> u32 {
+        elapsed_ms(self.now_ms(), earlier)
+    }
+}
+
+/// The real clock: `esp_timer`, via `crate::now_ms()`.
+#[cfg(not(test))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+#[cfg(not(test))]
+impl Clock for SystemClock {
+    #[inline]
+    fn now_ms(&self) -> u32 {
+        crate::now_ms()
+    }
+}
+
+/// Milliseconds from `earlier` to `now`, two `now_ms()` readings, correct
+/// across the wrap.
+#[inline]
+pub const fn elapsed_ms(now: u32, earlier: u32) -> u32 {
+    now.wrapping_sub(earlier)
+}
+
+/// Milliseconds since `earlier`, a `now_ms()` reading.
+#[cfg(not(test))]
+pub fn ms_since(earlier: u32) -> u32 {
+    elapsed_ms(crate::now_ms(), earlier)
+}
+
+/// Whole seconds since boot.  Unlike `now_ms()` this doesn't wrap (for
+/// ~136 years).
+#[cfg(not(test))]
+pub fn uptime_s() -> u32 {
+    unsafe { (esp_idf_sys::esp_timer_get_time() / 1_000_000) as u32 }
+}
+
+/// A clock that only moves when told to.
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct MockClock(core::cell::Cell<u32>);
+
+#[cfg(test)]
+impl MockClock {
+    pub fn starting_at(ms: u32) -> Self {
+        Self(core::cell::Cell::new(ms))
+    }
+
+    /// Step the clock forward `ms`, wrapping like the real one.
+    pub fn advance(&self, ms: u32) {
+        self.0.set(self.0.get().wrapping_add(ms));
+    }
+}
+
+#[cfg(test)]
+impl Clock for MockClock {
+    fn now_ms(&self) -> u32 {
+        self.0.get()
+    }
+}
+
+#[cfg(test)]
+impl Clock for &MockClock {
+    fn now_ms(&self) -> u32 {
+        self.0.get()
+    }
+}
+
+// The boundary case: a reading just before the wrap and one just after.
+const _: () = assert!(elapsed_ms(0x0000_0010, 0xFFFF_FFF0) == 0x20);
+const _: () = assert!(elapsed_ms(5, 5) == 0);
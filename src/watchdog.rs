@@ -0,0 +1,78 @@
+// PlastiWatch V2 — Task Watchdog
+//
+// A task stuck forever (e.g. blocked on the I2C mutex behind a wedged bus
+// transaction) would otherwise hang the watch until the battery is pulled.
+// The ESP-IDF task watchdog turns that into a reboot: `init` sets it to
+// panic (and so reset) after `WATCHDOG_TIMEOUT_MS`, and each long-running
+// task calls `subscribe` once and `feed`s the returned handle every loop
+// iteration.  If any subscribed task goes `WATCHDOG_TIMEOUT_MS` without
+// feeding, the chip resets.
+//
+// Watched: sensor, AI, UI and power.  The console, BLE and haptic threads
+// legitimately block for as long as nobody types, connects or buzzes, so
+// they are not subscribed.  A task that exits drops its handle, which
+// unsubscribes it, so a deliberate shutdown doesn't trip the reset.
+
+use crate::config::*;
+
+/// Configure the task watchdog.  Call once at boot, before any `subscribe`.
+pub fn init() {
+    let config = esp_idf_sys::esp_task_wdt_config_t {
+        timeout_ms: WATCHDOG_TIMEOUT_MS,
+        idle_core_mask: 0, // Only our tasks, not the idle task
+        trigger_panic: true,
+    };
+    unsafe {
+        // The bootloader config usually starts it already; otherwise init.
+        let mut ret = esp_idf_sys::esp_task_wdt_reconfigure(&config);
+        if ret == esp_idf_sys::ESP_ERR_INVALID_STATE as i32 {
+            ret = esp_idf_sys::esp_task_wdt_init(&config);
+        }
+        if ret == esp_idf_sys::ESP_OK {
+            log::info!("Task watchdog: {} ms", WATCHDOG_TIMEOUT_MS);
+        } else {
+            log::error!("Task watchdog setup failed ({}) — hung tasks won't reset", ret);
+        }
+    }
+}
+
+/// Subscription of the calling task.  Unsubscribes on drop.
+pub struct Watchdog {
+    name: &'static str,
+    subscribed: bool,
+}
+
+/// Add the calling task to the watchdog.  On failure the handle is inert
+/// (feeding it does nothing) rather than aborting the task.
+pub fn subscribe(name: &'static str) -> Watchdog {
+    let ret = unsafe { esp_idf_sys::esp_task_wdt_add(core::ptr::null_mut()) };
+    if ret != esp_idf_sys::ESP_OK {
+        log::warn!("{} task not watched ({})", name, ret);
+    }
+    Watchdog {
+        name,
+        subscribed: ret == esp_idf_sys::ESP_OK,
+    }
+}
+
+impl Watchdog {
+    /// Tell the watchdog this task is still making progress.
+    pub fn feed(&self) {
+        if self.subscribed {
+            unsafe {
+                esp_idf_sys::esp_task_wdt_reset();
+            }
+        }
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        if self.subscribed {
+            unsafe {
+                esp_idf_sys::esp_task_wdt_delete(core::ptr::null_mut());
+            }
+            log::info!("{} task no longer watched", self.name);
+        }
+    }
+}
@@ -0,0 +1,62 @@
+// PlastiWatch V2 — Wear Detection
+//
+// A watch on a wrist is never perfectly still: pulse, breathing and small
+// postural adjustments keep the accelerometer magnitude jittering by a few
+// milli-g even while the wearer sits motionless.  A watch lying on a table
+// shows only the sensor's own noise floor.
+//
+// `WearDetector` collects |a| over `WEAR_WINDOW_SAMPLES` samples and compares
+// its variance against `WEAR_MIN_ACCEL_VARIANCE`.  Above it the watch is
+// considered worn (`SystemState::worn`), which holds off the inactivity sleep
+// during long idle stretches.  Raise the threshold if a watch left on a desk
+// (fan, typing) keeps itself awake.
+
+use crate::config::*;
+use crate::events::SensorData;
+
+pub struct WearDetector {
+    count: usize,
+    sum: f32,
+    sum_sq: f32,
+}
+
+impl WearDetector {
+    pub fn new() -> Self {
+        Self {
+            count: 0,
+            sum: 0.0,
+            sum_sq: 0.0,
+        }
+    }
+
+    /// Feed one IMU sample.  Returns `Some(worn)` each time a window
+    /// completes, `None` in between.
+    pub fn update(&mut self, data: &SensorData) -> Option<bool> {
+        let magnitude = (data.ax * data.ax + data.ay * data.ay + data.az * data.az).sqrt();
+
+        // Accumulate the deviation from 1 g rather than |a| itself so the
+        // squared sum doesn't lose the milli-g jitter to f32 rounding.
+        let d = magnitude - 1.0;
+        self.sum += d;
+        self.sum_sq += d * d;
+        self.count += 1;
+        if self.count < WEAR_WINDOW_SAMPLES {
+            return None;
+        }
+
+        let n = self.count as f32;
+        let mean = self.sum / n;
+        let variance = (self.sum_sq / n - mean * mean).max(0.0);
+        let worn = variance >= WEAR_MIN_ACCEL_VARIANCE;
+        log::debug!(
+            "Wear check: var(|a|) = {:.6} g² → {}",
+            variance,
+            if worn { "worn" } else { "off wrist" }
+        );
+
+        self.count = 0;
+        self.sum = 0.0;
+        self.sum_sq = 0.0;
+        Some(worn)
+    }
+}